@@ -0,0 +1,176 @@
+use crate::cube::{
+	cubiecube::{CubieCube, Cycle, Ori},
+	sequence::Sequence,
+	Corner, Edge, RubiksCube, Side,
+};
+
+/// The corners of the first two layers
+const F2L_CORNERS: [Corner; 4] = [Corner::DLF, Corner::DFR, Corner::DRB, Corner::DBL];
+/// The edges of the first two layers
+const F2L_EDGES: [Edge; 8] = [
+	Edge::DF,
+	Edge::DR,
+	Edge::DB,
+	Edge::DL,
+	Edge::FR,
+	Edge::BR,
+	Edge::BL,
+	Edge::FL,
+];
+
+/// What a sequence does to the solved cube
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Analysis {
+	pub sequence: Sequence,
+	/// How often the sequence must be applied, until the cube is solved again
+	pub order: usize,
+	/// The cycles of corners, which change their position
+	pub corner_cycles: Vec<Cycle<Corner>>,
+	/// The cycles of edges, which change their position
+	pub edge_cycles: Vec<Cycle<Edge>>,
+	/// The cycles of centers, which change their position
+	pub center_cycles: Vec<Vec<Side>>,
+	/// The positions of the twisted corners with their twist (1 is clockwise)
+	pub twisted_corners: Vec<(Corner, Ori)>,
+	/// The positions of the flipped edges
+	pub flipped_edges: Vec<Edge>,
+	/// Whether the first two layers (the D layer and the E slice) are unchanged
+	pub preserves_f2l: bool,
+}
+
+impl Analysis {
+	pub fn new(sequence: &Sequence) -> Self {
+		let mut cube = CubieCube::new();
+		for turn in sequence.turns.iter() {
+			cube.apply_turn(*turn);
+		}
+
+		let solved = CubieCube::new();
+		let preserves_f2l = cube.centers == solved.centers
+			&& F2L_CORNERS
+				.iter()
+				.all(|c| cube.corner_at(*c) == solved.corner_at(*c))
+			&& F2L_EDGES
+				.iter()
+				.all(|e| cube.edge_at(*e) == solved.edge_at(*e));
+
+		Self {
+			sequence: sequence.clone(),
+			order: cube.order(),
+			corner_cycles: cube
+				.corner_cycles()
+				.into_iter()
+				.filter(|c| c.positions.len() > 1)
+				.collect(),
+			edge_cycles: cube
+				.edge_cycles()
+				.into_iter()
+				.filter(|c| c.positions.len() > 1)
+				.collect(),
+			center_cycles: cube.center_cycles(),
+			twisted_corners: cube
+				.corners
+				.iter()
+				.enumerate()
+				.filter(|(_, (_, o))| *o != 0)
+				.map(|(i, (_, o))| (Corner::from_repr(i).unwrap(), *o))
+				.collect(),
+			flipped_edges: cube
+				.edges
+				.iter()
+				.enumerate()
+				.filter(|(_, (_, o))| *o != 0)
+				.map(|(i, _)| Edge::from_repr(i).unwrap())
+				.collect(),
+			preserves_f2l,
+		}
+	}
+}
+
+/// Write the list, or "none" if it's empty
+fn write_list<T>(
+	f: &mut std::fmt::Formatter,
+	list: &[T],
+	write: impl Fn(&mut std::fmt::Formatter, &T) -> std::fmt::Result,
+) -> std::fmt::Result {
+	if list.is_empty() {
+		return write!(f, "none");
+	}
+	for (i, item) in list.iter().enumerate() {
+		if i > 0 {
+			write!(f, " ")?;
+		}
+		write(f, item)?;
+	}
+	Ok(())
+}
+
+impl std::fmt::Display for Analysis {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		// A cycle is written like (URF UBR ULB)
+		fn cycle<T: std::fmt::Display>(
+			f: &mut std::fmt::Formatter,
+			c: &Cycle<T>,
+		) -> std::fmt::Result {
+			write!(f, "(")?;
+			write_list(f, &c.positions, |f, p| write!(f, "{}", p))?;
+			write!(f, ")")
+		}
+
+		writeln!(f, "Sequence:        {}", self.sequence)?;
+		writeln!(f, "Order:           {}", self.order)?;
+		write!(f, "Corner cycles:   ")?;
+		write_list(f, &self.corner_cycles, cycle)?;
+		write!(f, "\nEdge cycles:     ")?;
+		write_list(f, &self.edge_cycles, cycle)?;
+		write!(f, "\nCenter cycles:   ")?;
+		write_list(f, &self.center_cycles, |f, c| {
+			write!(f, "(")?;
+			write_list(f, c, |f, s| write!(f, "{:?}", s))?;
+			write!(f, ")")
+		})?;
+		write!(f, "\nTwisted corners: ")?;
+		write_list(f, &self.twisted_corners, |f, (c, o)| {
+			let wise = if *o == 1 {
+				"clockwise"
+			} else {
+				"counterclockwise"
+			};
+			write!(f, "{} ({})", c, wise)
+		})?;
+		write!(f, "\nFlipped edges:   ")?;
+		write_list(f, &self.flipped_edges, |f, e| write!(f, "{}", e))?;
+		let preserved = if self.preserves_f2l { "yes" } else { "no" };
+		write!(f, "\nPreserves F2L:   {}", preserved)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	/// Check the analysis of some well known algorithms
+	fn analyze_algorithms() {
+		let t_perm = Analysis::new(&"R U R' U' R' F R2 U' R' U' R U R' F'".parse().unwrap());
+		assert_eq!(t_perm.order, 2);
+		assert_eq!(t_perm.corner_cycles.len(), 1);
+		assert_eq!(t_perm.edge_cycles[0].positions, vec![Edge::UR, Edge::UL]);
+		assert!(t_perm.twisted_corners.is_empty());
+		assert!(t_perm.preserves_f2l);
+
+		let sune = Analysis::new(&"R U R' U R U2 R'".parse().unwrap());
+		assert_eq!(sune.order, 6);
+		assert_eq!(sune.twisted_corners.len(), 3);
+		assert!(sune.flipped_edges.is_empty());
+		assert!(sune.preserves_f2l);
+
+		let sexy = Analysis::new(&"R U R' U'".parse().unwrap());
+		assert!(!sexy.preserves_f2l);
+
+		let m = Analysis::new(&"M".parse().unwrap());
+		assert_eq!(m.center_cycles.len(), 1);
+		assert!(!m.preserves_f2l);
+	}
+}
@@ -1,11 +1,12 @@
 use std::{ops::Mul, str::FromStr};
 
-use crate::cube::*;
+use crate::cube::{completion::UNKNOWN, *};
 use strum::*;
 
 use const_for::const_for;
 
-const CUBEDATA_LEN: usize = CUBE_AREA * NUM_SIDES;
+/// The number of stickers of a cube
+pub const CUBEDATA_LEN: usize = CUBE_AREA * NUM_SIDES;
 
 type CubeData = [u8; CUBEDATA_LEN];
 
@@ -122,15 +123,55 @@ const T_RIGHT: CubeData = [
 	51, 48, 45, 52, 49, 46, 53, 50, 47, // right
 ];
 
+// The slices turn in the same direction as L, D and F respectively
+const T_MIDDLE: CubeData = [
+	0, 25, 2, 3, 22, 5, 6, 19, 8, // up
+	9, 28, 11, 12, 31, 14, 15, 34, 17, // down
+	18, 16, 20, 21, 13, 23, 24, 10, 26, // back
+	27, 1, 29, 30, 4, 32, 33, 7, 35, // front
+	36, 37, 38, 39, 40, 41, 42, 43, 44, // left
+	45, 46, 47, 48, 49, 50, 51, 52, 53, // right
+];
+
+const T_EQUATOR: CubeData = [
+	0, 1, 2, 3, 4, 5, 6, 7, 8, // up
+	9, 10, 11, 12, 13, 14, 15, 16, 17, // down
+	18, 19, 20, 48, 49, 50, 24, 25, 26, // back
+	27, 28, 29, 39, 40, 41, 33, 34, 35, // front
+	36, 37, 38, 21, 22, 23, 42, 43, 44, // left
+	45, 46, 47, 30, 31, 32, 51, 52, 53, // right
+];
+
+const T_STANDING: CubeData = [
+	0, 1, 2, 43, 40, 37, 6, 7, 8, // up
+	9, 10, 11, 52, 49, 46, 15, 16, 17, // down
+	18, 19, 20, 21, 22, 23, 24, 25, 26, // back
+	27, 28, 29, 30, 31, 32, 33, 34, 35, // front
+	36, 12, 38, 39, 13, 41, 42, 14, 44, // left
+	45, 3, 47, 48, 4, 50, 51, 5, 53, // right
+];
+
 const fn generate_transformation_table() -> [[CubeData; NUM_TURNWISES]; NUM_TURNTYPES] {
-	const BASE: [CubeData; NUM_SIDES] = [T_UP, T_DOWN, T_BACK, T_FRONT, T_LEFT, T_RIGHT];
+	const BASE: [CubeData; NUM_BASIC_TURNTYPES] = [
+		T_UP, T_DOWN, T_BACK, T_FRONT, T_LEFT, T_RIGHT, T_MIDDLE, T_EQUATOR, T_STANDING,
+	];
 
 	let mut out = [[T_BASE; NUM_TURNWISES]; NUM_TURNTYPES];
 
-	const_for!(i in 0..NUM_SIDES => {
-		out[i][0] = BASE[i];
+	const_for!(i in 0..NUM_TURNTYPES => {
+		out[i][0] = if i < NUM_BASIC_TURNTYPES {
+			BASE[i]
+		} else {
+			// Wide turns and rotations are made of the basic turns
+			let parts = TurnType::from_repr(i as u8).unwrap().parts();
+			let mut t = T_BASE;
+			const_for!(k in 0..parts.len() => {
+				t = chain_transform(t, out[parts[k].side as usize][parts[k].wise as usize]);
+			});
+			t
+		};
 		const_for!(j in 1..NUM_TURNWISES => {
-			out[i][j] = chain_transform(out[i][j-1], BASE[i]);
+			out[i][j] = chain_transform(out[i][j-1], out[i][0]);
 		});
 	});
 
@@ -146,7 +187,8 @@ fn convert_vec_to_transformation(turns: &Vec<Turn>) -> CubeData {
 
 	for turn in turns {
 		let t = TRANSFORM[turn.side as usize][turn.wise as usize];
-		out = chain_transform(t, out);
+		// The turn is applied after the previous ones
+		out = chain_transform(out, t);
 	}
 
 	out
@@ -164,26 +206,68 @@ pub enum FromStrError {
 	CornerOrder(Corner),
 	#[error("The edge at position {0} has a invalid color combination")]
 	Edge(Edge),
+	#[error("Not every color appears on exactly one center")]
+	Centers,
+	#[error("'{color}' at position {position} is not a valid color")]
+	Color { color: char, position: usize },
+	#[error("The centers of the sides {first:?} and {second:?} both have the color {color}")]
+	DuplicateCenter {
+		color: char,
+		first: Side,
+		second: Side,
+	},
+	#[error("The color {color} appears {count} times instead of {}", CUBE_AREA)]
+	ColorCount { color: char, count: usize },
 }
 
+/// The order of the sides in a facelet string of Kociemba
+const KOCIEMBA_ORDER: [Side; NUM_SIDES] = [
+	Side::Up,
+	Side::Right,
+	Side::Front,
+	Side::Down,
+	Side::Left,
+	Side::Back,
+];
+
+/// The letter of each side (in the order of `Side`)
+const SIDE_LETTERS: [char; NUM_SIDES] = ['U', 'D', 'B', 'F', 'L', 'R'];
+
+/// The letters of the sticker colors, in the order of the sides of the default color scheme
+/// (white up, yellow down, green back, blue front, red left and orange right)
+const COLOR_LETTERS: [char; NUM_SIDES] = ['W', 'Y', 'G', 'B', 'R', 'O'];
+
 impl FromStr for ArrayCube {
 	type Err = FromStrError;
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		if s.len() != CUBEDATA_LEN {
+		let chars: Vec<char> = s.chars().collect();
+		if chars.len() != CUBEDATA_LEN {
 			return Err(FromStrError::Length);
 		}
 
 		let mut cube = ArrayCube::new();
 
 		// Parse the colors from the string
-		for (i, c) in s.as_bytes().iter().enumerate() {
-			cube.data[i] = (c - b'a') * CUBE_AREA as u8;
+		for (i, c) in chars.into_iter().enumerate() {
+			if !('a'..='f').contains(&c) {
+				return Err(FromStrError::Color {
+					color: c,
+					position: i + 1,
+				});
+			}
+			cube.data[i] = (c as u8 - b'a') * CUBE_AREA as u8;
 		}
 
-		// The center pieces have a fixed index
+		// Each center piece has a single facelet, so its color is its index
+		let mut has_center = [false; NUM_SIDES];
 		for i in (4..54).step_by(CUBE_AREA) {
-			cube.data[i] = i as u8;
+			let side = cube.data[i] as usize / CUBE_AREA;
+			if side >= NUM_SIDES || has_center[side] {
+				return Err(FromStrError::Centers);
+			}
+			has_center[side] = true;
+			cube.data[i] += 4;
 		}
 
 		for pos in Corner::iter() {
@@ -237,6 +321,22 @@ impl From<ArrayCube> for String {
 	}
 }
 
+/// Cubes are serialized as the string of their colors (see `FromStr`)
+#[cfg(feature = "serde")]
+impl serde::Serialize for ArrayCube {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&String::from(self.clone()))
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ArrayCube {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		ArrayCube::from_str(&s).map_err(serde::de::Error::custom)
+	}
+}
+
 impl RubiksCube for ArrayCube {
 	fn apply_turn(&mut self, turn: Turn) {
 		// Get the transformation matrix (which is easy because it's carefully sorted)
@@ -355,6 +455,156 @@ impl ArrayCube {
 		}
 	}
 
+	/// Parse a cube from a facelet string as used by Kociemba's tools and cubing.js.
+	/// The sides are in the order U R F D L B and each facelet is the letter (URFDLB)
+	/// of the side its color belongs to.
+	pub fn from_kociemba(s: &str) -> Result<Self, FromStrError> {
+		Self::from_str(&Self::kociemba_to_native(s)?)
+	}
+
+	/// Rewrite a facelet string of Kociemba to the native format, without checking the cube
+	pub(crate) fn kociemba_to_native(s: &str) -> Result<String, FromStrError> {
+		let chars: Vec<char> = s.chars().collect();
+		if chars.len() != CUBEDATA_LEN {
+			return Err(FromStrError::Length);
+		}
+
+		// Rewrite it to the native format
+		let mut native = ['a'; CUBEDATA_LEN];
+		for (i, c) in chars.into_iter().enumerate() {
+			let color = match SIDE_LETTERS.iter().position(|l| *l == c) {
+				Some(color) => (b'a' + color as u8) as char,
+				None if c == UNKNOWN => UNKNOWN,
+				None => {
+					return Err(FromStrError::Color {
+						color: c,
+						position: i + 1,
+					})
+				}
+			};
+			let side = KOCIEMBA_ORDER[i / CUBE_AREA] as usize;
+			native[side * CUBE_AREA + i % CUBE_AREA] = color;
+		}
+
+		Ok(native.iter().collect())
+	}
+
+	/// Return the facelet string of Kociemba (see `from_kociemba`)
+	pub fn to_kociemba(&self) -> String {
+		Self::native_to_kociemba(&String::from(self.clone()))
+	}
+
+	/// Rewrite a string in the native format (which may contain unknown stickers)
+	/// to the facelet string of Kociemba
+	pub(crate) fn native_to_kociemba(s: &str) -> String {
+		let chars: Vec<char> = s.chars().collect();
+		KOCIEMBA_ORDER
+			.iter()
+			.flat_map(|side| (0..CUBE_AREA).map(move |i| *side as usize * CUBE_AREA + i))
+			.map(|i| match chars[i] {
+				UNKNOWN => UNKNOWN,
+				c => SIDE_LETTERS[(c as u8 - b'a') as usize],
+			})
+			.collect()
+	}
+
+	/// Parse a cube from the sticker colors WYGBRO (case insensitive), with the sides in the
+	/// order U D B F L R. The side of each color is the side of the center with this color,
+	/// so any color scheme and orientation of the cube can be entered.
+	pub fn from_colors(s: &str) -> Result<Self, FromStrError> {
+		let native = Self::colors_to_native(s)?;
+
+		for letter in COLOR_LETTERS {
+			let count = s
+				.chars()
+				.filter(|c| c.to_ascii_uppercase() == letter)
+				.count();
+			if count != CUBE_AREA {
+				return Err(FromStrError::ColorCount {
+					color: letter,
+					count,
+				});
+			}
+		}
+
+		Self::from_str(&native)
+	}
+
+	/// Rewrite sticker colors to the native format (with the color scheme inferred from
+	/// the centers), without checking the cube
+	pub(crate) fn colors_to_native(s: &str) -> Result<String, FromStrError> {
+		let chars: Vec<char> = s.chars().map(|c| c.to_ascii_uppercase()).collect();
+		if chars.len() != CUBEDATA_LEN {
+			return Err(FromStrError::Length);
+		}
+
+		let mut colors = [None; CUBEDATA_LEN];
+		for (i, c) in chars.iter().enumerate() {
+			colors[i] = match COLOR_LETTERS.iter().position(|l| l == c) {
+				Some(color) => Some(color),
+				None if *c == UNKNOWN => None,
+				None => {
+					return Err(FromStrError::Color {
+						color: s.chars().nth(i).unwrap(),
+						position: i + 1,
+					})
+				}
+			};
+		}
+
+		// Infer the color scheme from the centers
+		let mut scheme: [Option<Side>; NUM_SIDES] = [None; NUM_SIDES];
+		let mut unknown = vec![];
+		for side in Side::iter() {
+			let Some(color) = colors[side as usize * CUBE_AREA + 4] else {
+				unknown.push(side);
+				continue;
+			};
+			if let Some(first) = scheme[color] {
+				return Err(FromStrError::DuplicateCenter {
+					color: COLOR_LETTERS[color],
+					first,
+					second: side,
+				});
+			}
+			scheme[color] = Some(side);
+		}
+
+		// A single unknown center has the remaining color
+		match unknown.as_slice() {
+			[] => {}
+			[side] => {
+				let color = scheme.iter().position(|s| s.is_none()).unwrap();
+				scheme[color] = Some(*side);
+			}
+			_ => return Err(FromStrError::Centers),
+		}
+
+		Ok(colors
+			.iter()
+			.map(|c| match c {
+				Some(c) => (b'a' + scheme[*c].unwrap() as u8) as char,
+				None => UNKNOWN,
+			})
+			.collect())
+	}
+
+	/// Return the sticker colors in the default color scheme (see `from_colors`)
+	pub fn to_colors(&self) -> String {
+		Self::native_to_colors(&String::from(self.clone()))
+	}
+
+	/// Rewrite a string in the native format (which may contain unknown stickers)
+	/// to the sticker colors in the default color scheme
+	pub(crate) fn native_to_colors(s: &str) -> String {
+		s.chars()
+			.map(|c| match c {
+				UNKNOWN => UNKNOWN,
+				c => COLOR_LETTERS[(c as u8 - b'a') as usize],
+			})
+			.collect()
+	}
+
 	/// Return the color at IDX
 	pub fn color_at(&self, idx: usize) -> Side {
 		Side::from_repr(self.data[idx] / CUBE_AREA as u8).unwrap()
@@ -578,8 +828,8 @@ pub fn get_symmetry(cube: &ArrayCube, i: usize) -> ArrayCube {
 mod tests {
 	use std::str::FromStr;
 
-	use crate::cube::arraycube::ArrayCube;
-	use crate::cube::*;
+	use crate::cube::arraycube::{ArrayCube, FromStrError};
+	use crate::cube::{notation::parse_turns, *};
 	use arraycube::{
 		chain_transform, get_symmetry, is_base, CubeData, CUBEDATA_LEN, NUM_SYMMETRIES, SYMMETRIES,
 		SYMMETRY_INVERSE, TRANSFORM,
@@ -631,6 +881,73 @@ mod tests {
 		}
 	}
 
+	#[test]
+	/// Test the conversion from and to the facelet strings of Kociemba
+	fn kociemba_format() {
+		const SOLVED: &str = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+		assert_eq!(
+			ArrayCube::from_kociemba(SOLVED).unwrap(),
+			ArrayCube::default()
+		);
+		assert_eq!(ArrayCube::default().to_kociemba(), SOLVED);
+
+		let mut cube = ArrayCube::default();
+		cube.apply_turns(parse_turns("R").unwrap());
+		let s = "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB";
+		assert_eq!(cube.to_kociemba(), s);
+		assert_eq!(ArrayCube::from_kociemba(s).unwrap(), cube);
+
+		cube.apply_turns(random_sequence(20));
+		assert_eq!(ArrayCube::from_kociemba(&cube.to_kociemba()).unwrap(), cube);
+
+		assert!(matches!(
+			ArrayCube::from_kociemba(&SOLVED.replace("UUUUU", "UUUUX")),
+			Err(FromStrError::Color {
+				color: 'X',
+				position: 5
+			})
+		));
+	}
+
+	#[test]
+	/// Test the color format with its inferred color scheme
+	fn color_format() {
+		let mut cube = ArrayCube::default();
+		cube.apply_turns(random_sequence(20));
+		let colors = cube.to_colors();
+		assert_eq!(
+			ArrayCube::from_colors(&colors.to_lowercase()).unwrap(),
+			cube
+		);
+
+		// Holding the cube in another orientation only changes the scheme
+		let mut rotated = ArrayCube::default();
+		rotated.apply_turns(parse_turns("x y2 R U").unwrap());
+		let mut turned = ArrayCube::default();
+		turned.apply_turns(parse_turns("R U").unwrap());
+		assert_eq!(
+			ArrayCube::from_colors(&rotated.to_colors()).unwrap(),
+			turned
+		);
+
+		let solved = ArrayCube::default().to_colors();
+		assert!(matches!(
+			ArrayCube::from_colors(&solved.replacen("WWWWW", "WWWWY", 1)),
+			Err(FromStrError::DuplicateCenter {
+				color: 'Y',
+				first: Side::Up,
+				second: Side::Down,
+			})
+		));
+		assert!(matches!(
+			ArrayCube::from_colors(&solved.replacen("WW", "WY", 1)),
+			Err(FromStrError::ColorCount {
+				color: 'W',
+				count: 8
+			})
+		));
+	}
+
 	#[test]
 	/// Test for more basic turning
 	fn array_cube_turns2() {
@@ -644,6 +961,25 @@ mod tests {
 		assert_eq!(cube, bef);
 	}
 
+	#[test]
+	/// Test the slice turns against equivalent sequences of outer turns
+	fn array_cube_slices() {
+		// The checkerboard pattern
+		let slices = ArrayCube::from(parse_turns("M2 E2 S2").unwrap());
+		let sides = ArrayCube::from(parse_turns("U2 D2 F2 B2 L2 R2").unwrap());
+		assert_eq!(String::from(slices), String::from(sides));
+
+		// H-permutation
+		let slices = ArrayCube::from(parse_turns("M2 U M2 U2 M2 U M2").unwrap());
+		let sides = ArrayCube::from(parse_turns("R2 U2 R U2 R2 U2 R2 U2 R U2 R2").unwrap());
+		assert_eq!(slices, sides);
+
+		// Slices move the centers, which have to survive the string conversion
+		let cube = ArrayCube::from(parse_turns("M E' S2").unwrap());
+		let s: String = cube.clone().into();
+		assert_eq!(ArrayCube::from_str(&s).unwrap(), cube);
+	}
+
 	#[test]
 	/// Test for corner parsing
 	fn corner_edge_checking() {
@@ -674,6 +1010,7 @@ mod tests {
 	// ===== Transformation checks =====
 
 	/// Check whether given transformation is a permutation
+	#[allow(clippy::needless_range_loop)]
 	fn check_permutation(perm: CubeData) -> bool {
 		let mut has_num = [false; CUBEDATA_LEN];
 
@@ -690,6 +1027,7 @@ mod tests {
 
 	#[test]
 	/// Test that every transformation permutation are actually permutations
+	#[allow(clippy::needless_range_loop)]
 	fn permutation_test() {
 		for i in 0..NUM_TURNTYPES {
 			for j in 0..NUM_TURNWISES {
@@ -700,12 +1038,24 @@ mod tests {
 
 	#[test]
 	/// Test that every transformation permutation are actually permutations
+	#[allow(clippy::needless_range_loop)]
 	fn symmetry_permutation_test() {
 		for i in 0..NUM_SYMMETRIES {
 			assert!(check_permutation(SYMMETRIES[i]));
 		}
 	}
 
+	#[test]
+	/// The transformation of a sequence applies its turns in order
+	fn sequence_transformation_test() {
+		let turns = parse_turns("R U F' L2 D").unwrap();
+		let mut turned = ArrayCube::default();
+		for turn in turns.iter() {
+			turned.apply_turn(*turn);
+		}
+		assert_eq!(ArrayCube::from(turns), turned);
+	}
+
 	#[test]
 	/// Test whether all symmetries have an inverse
 	fn symmetry_inverse_test() {
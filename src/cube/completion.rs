@@ -0,0 +1,273 @@
+use std::str::FromStr;
+
+use strum::IntoEnumIterator;
+
+use super::{
+	arraycube::{ArrayCube, FromStrError, CUBEDATA_LEN},
+	diagnosis::{corner_stickers, edge_stickers},
+	placement::Placements,
+	Corner, Edge, Side, CUBE_AREA,
+};
+
+/// The character of a sticker with an unknown color in a cube string
+pub const UNKNOWN: char = '?';
+
+/// The cubes, which fit to a partially known set of stickers
+pub struct Completion {
+	/// The stickers, with every sticker filled in which has the same color in all completions
+	pub stickers: Vec<Option<Side>>,
+	/// Whether every known center has the color of its side (the centers can't move)
+	centers_fit: bool,
+	corners: Placements<u128>,
+	edges: Placements<u128>,
+}
+
+impl Completion {
+	/// Find the completions of the stickers (in the order of the cube string),
+	/// where None is an unknown sticker
+	pub fn new(stickers: &[Option<Side>]) -> Self {
+		debug_assert_eq!(stickers.len(), CUBEDATA_LEN);
+
+		// Whether the sticker at the index could have the color
+		let fits = |(i, color): &(usize, Side)| stickers[*i].is_none_or(|c| c == *color);
+
+		let corner_candidates = Corner::iter()
+			.map(|pos| {
+				Corner::iter()
+					.flat_map(|corner| (0..3).map(move |ori| (corner, ori)))
+					.filter(|(corner, ori)| corner_stickers(pos, *corner, *ori).iter().all(fits))
+					.map(|(corner, ori)| (corner as usize, ori, 1))
+					.collect()
+			})
+			.collect();
+		let edge_candidates = Edge::iter()
+			.map(|pos| {
+				Edge::iter()
+					.flat_map(|edge| (0..2).map(move |ori| (edge, ori)))
+					.filter(|(edge, ori)| edge_stickers(pos, *edge, *ori).iter().all(fits))
+					.map(|(edge, ori)| (edge as usize, ori, 1))
+					.collect()
+			})
+			.collect();
+
+		let centers_fit = Side::iter().all(|side| fits(&(side as usize * CUBE_AREA + 4, side)));
+
+		let mut completion = Self {
+			stickers: stickers.to_vec(),
+			centers_fit,
+			corners: Placements::new(corner_candidates, 3),
+			edges: Placements::new(edge_candidates, 2),
+		};
+		completion.fill();
+		completion
+	}
+
+	/// Parse the stickers from a string in the native format, where '?' is an unknown sticker
+	pub fn from_native(s: &str) -> Result<Self, FromStrError> {
+		let chars: Vec<char> = s.chars().collect();
+		if chars.len() != CUBEDATA_LEN {
+			return Err(FromStrError::Length);
+		}
+
+		let mut stickers = vec![];
+		for (i, c) in chars.into_iter().enumerate() {
+			stickers.push(match c {
+				UNKNOWN => None,
+				'a'..='f' => Side::from_repr(c as u8 - b'a'),
+				_ => {
+					return Err(FromStrError::Color {
+						color: c,
+						position: i + 1,
+					})
+				}
+			});
+		}
+
+		Ok(Self::new(&stickers))
+	}
+
+	/// Return the stickers in the native format, where '?' is an unknown sticker
+	pub fn to_native(&self) -> String {
+		self.stickers
+			.iter()
+			.map(|c| match c {
+				Some(c) => (b'a' + *c as u8) as char,
+				None => UNKNOWN,
+			})
+			.collect()
+	}
+
+	/// Fill in every sticker, which has the same color in all completions
+	fn fill(&mut self) {
+		// The centers can't move
+		for side in Side::iter() {
+			let i = side as usize * CUBE_AREA + 4;
+			if self.stickers[i].is_none() {
+				self.stickers[i] = Some(side);
+			}
+		}
+
+		if self.count() == 0 {
+			return;
+		}
+
+		// The number of completions using each candidate
+		let usage = |own: &Placements<u128>, other: &Placements<u128>| -> Vec<Vec<u128>> {
+			let [even, odd] = [0, 1].map(|parity| own.usage(parity));
+			even.iter()
+				.zip(odd.iter())
+				.map(|(even, odd)| {
+					even.iter()
+						.zip(odd.iter())
+						.map(|(e, o)| e * other.total(0) + o * other.total(1))
+						.collect()
+				})
+				.collect()
+		};
+
+		let corner_usage = usage(&self.corners, &self.edges);
+		for (pos, usage) in Corner::iter().zip(corner_usage) {
+			let stickers = self.corners.candidates[pos as usize]
+				.iter()
+				.zip(usage)
+				.filter(|(_, count)| *count != 0)
+				.map(|((corner, ori, _), _)| {
+					corner_stickers(pos, Corner::from_repr(*corner).unwrap(), *ori).to_vec()
+				})
+				.collect();
+			self.fill_forced(stickers);
+		}
+
+		let edge_usage = usage(&self.edges, &self.corners);
+		for (pos, usage) in Edge::iter().zip(edge_usage) {
+			let stickers = self.edges.candidates[pos as usize]
+				.iter()
+				.zip(usage)
+				.filter(|(_, count)| *count != 0)
+				.map(|((edge, ori, _), _)| {
+					edge_stickers(pos, Edge::from_repr(*edge).unwrap(), *ori).to_vec()
+				})
+				.collect();
+			self.fill_forced(stickers);
+		}
+	}
+
+	/// Fill in the stickers of a piece, which are the same for all its possible placements
+	fn fill_forced(&mut self, placements: Vec<Vec<(usize, Side)>>) {
+		for (k, (i, color)) in placements[0].iter().enumerate() {
+			if placements.iter().all(|stickers| stickers[k].1 == *color) {
+				self.stickers[*i] = Some(*color);
+			}
+		}
+	}
+
+	/// Return the number of solvable cubes, which fit to the stickers
+	pub fn count(&self) -> u128 {
+		if !self.centers_fit {
+			return 0;
+		}
+		(0..2)
+			.map(|parity| self.corners.total(parity) * self.edges.total(parity))
+			.sum()
+	}
+
+	/// Return true if the color of every sticker is known
+	pub fn is_complete(&self) -> bool {
+		self.stickers.iter().all(|c| c.is_some())
+	}
+
+	/// Return an iterator over all solvable cubes, which fit to the stickers
+	pub fn completions(&self) -> impl Iterator<Item = ArrayCube> + '_ {
+		let parities = if self.centers_fit { 0..2 } else { 0..0 };
+		parities.flat_map(move |parity| {
+			self.corners.iter(parity).flat_map(move |corners| {
+				self.edges
+					.iter(parity)
+					.map(move |edges| self.build(&corners, &edges))
+			})
+		})
+	}
+
+	/// Build the cube with the placed pieces
+	fn build(&self, corners: &[(usize, usize)], edges: &[(usize, usize)]) -> ArrayCube {
+		let mut colors: Vec<Side> = (0..CUBEDATA_LEN)
+			.map(|i| Side::from_repr((i / CUBE_AREA) as u8).unwrap())
+			.collect();
+
+		for (pos, (corner, ori)) in Corner::iter().zip(corners) {
+			for (i, color) in corner_stickers(pos, Corner::from_repr(*corner).unwrap(), *ori) {
+				colors[i] = color;
+			}
+		}
+		for (pos, (edge, ori)) in Edge::iter().zip(edges) {
+			for (i, color) in edge_stickers(pos, Edge::from_repr(*edge).unwrap(), *ori) {
+				colors[i] = color;
+			}
+		}
+
+		let s: String = colors.iter().map(|c| (b'a' + *c as u8) as char).collect();
+		ArrayCube::from_str(&s).expect("A completion is always a valid cube")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cube::{
+		arraycube::{corner_to_indices, edge_to_indices},
+		cubiecube::CubieCube,
+	};
+
+	/// Return the stickers of the cube with the given ones unknown
+	fn stickers(cube: &ArrayCube, unknown: &[usize]) -> Vec<Option<Side>> {
+		(0..CUBEDATA_LEN)
+			.map(|i| (!unknown.contains(&i)).then(|| cube.color_at(i)))
+			.collect()
+	}
+
+	#[test]
+	/// The number of all cubes and the forced stickers of a single piece
+	fn count_completions() {
+		let completion = Completion::new(&[None; CUBEDATA_LEN]);
+		assert_eq!(completion.count(), 43_252_003_274_489_856_000);
+		assert!(!completion.is_complete());
+
+		// A single corner and edge are forced by the other pieces
+		let mut cube = ArrayCube::default();
+		cube.apply_turns(crate::parse_turns("R U R' F2 D").unwrap());
+		let corner: [usize; 3] = corner_to_indices(Corner::DBL).into();
+		let edge: [usize; 2] = edge_to_indices(Edge::FR).into();
+		let completion = Completion::new(&stickers(&cube, &[&corner[..], &edge[..]].concat()));
+		assert_eq!(completion.count(), 1);
+		assert!(completion.is_complete());
+		assert_eq!(completion.completions().collect::<Vec<_>>(), vec![cube]);
+	}
+
+	#[test]
+	/// Two unknown corners can only be twisted against each other
+	fn enumerate_completions() {
+		let cube = ArrayCube::default();
+		let a: [usize; 3] = corner_to_indices(Corner::URF).into();
+		let b: [usize; 3] = corner_to_indices(Corner::DBL).into();
+		let completion = Completion::new(&stickers(&cube, &[a, b].concat()));
+		assert_eq!(completion.count(), 3);
+
+		let cubes: Vec<ArrayCube> = completion.completions().collect();
+		assert_eq!(cubes.len(), 3);
+		assert!(cubes.contains(&cube));
+		for cube in cubes {
+			assert!(CubieCube::try_from(cube).unwrap().is_solvable());
+		}
+	}
+
+	#[test]
+	/// A known center is never replaced, a wrong one leaves no fitting cube
+	fn wrong_center() {
+		let mut stickers = stickers(&ArrayCube::default(), &[0, 1, 2]);
+		stickers[4] = Some(Side::Down);
+		let completion = Completion::new(&stickers);
+		assert_eq!(completion.count(), 0);
+		assert_eq!(completion.completions().count(), 0);
+		assert_eq!(completion.stickers[4], Some(Side::Down));
+	}
+}
@@ -8,6 +8,7 @@ pub type Ori = u32; // and the blind forest
 
 type CornerList = [(Corner, Ori); NUM_CORNERS];
 type EdgeList = [(Edge, Ori); NUM_EDGES];
+type CenterList = [Side; NUM_SIDES];
 
 /// The cube specification as Kociemba published in
 /// https://kociemba.org/math/cubielevel.htm
@@ -15,9 +16,32 @@ type EdgeList = [(Edge, Ori); NUM_EDGES];
 /// Uses more space than necessary, but gives
 /// very good insights about the cubes properties.
 #[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CubieCube {
 	pub corners: CornerList,
 	pub edges: EdgeList,
+	/// The center at each side. Only slice turns move them.
+	pub centers: CenterList,
+}
+
+/// A cycle of pieces. The piece at positions\[i\] moves to positions\[i+1\],
+/// and the piece at the last position moves to the first one.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cycle<T> {
+	pub positions: Vec<T>,
+	/// The twist (corners) or flip (edges) a piece gains by going around the cycle once
+	pub orientation: Ori,
+}
+
+impl<T> Cycle<T> {
+	/// Return how often the cycle must be applied until its pieces are solved
+	pub fn order(&self, num_orientations: Ori) -> usize {
+		match self.orientation {
+			0 => self.positions.len(),
+			_ => self.positions.len() * num_orientations as usize,
+		}
+	}
 }
 
 // ===== Tranformation-Corners =====
@@ -91,12 +115,25 @@ const fn chain_corners(t1: CornerList, t2: CornerList) -> CornerList {
 
 /// Generate the transformation table for the corners
 const fn generate_corner_transform_table() -> [[CornerList; NUM_TURNWISES]; NUM_TURNTYPES] {
-	const BASE: [CornerList; NUM_SIDES] = [TC_UP, TC_DOWN, TC_BACK, TC_FRONT, TC_LEFT, TC_RIGHT];
+	// The slices don't move any corners
+	const BASE: [CornerList; NUM_BASIC_TURNTYPES] = [
+		TC_UP, TC_DOWN, TC_BACK, TC_FRONT, TC_LEFT, TC_RIGHT, TC_BASE, TC_BASE, TC_BASE,
+	];
 
 	let mut out = [[TC_BASE; NUM_TURNWISES]; NUM_TURNTYPES];
 
-	const_for!(i in 0..NUM_SIDES => {
-		out[i][0] = BASE[i];
+	const_for!(i in 0..NUM_TURNTYPES => {
+		out[i][0] = if i < NUM_BASIC_TURNTYPES {
+			BASE[i]
+		} else {
+			// Wide turns and rotations are made of the basic turns
+			let parts = TurnType::from_repr(i as u8).unwrap().parts();
+			let mut t = TC_BASE;
+			const_for!(k in 0..parts.len() => {
+				t = chain_corners(t, out[parts[k].side as usize][parts[k].wise as usize]);
+			});
+			t
+		};
 		out[i][1] = chain_corners(out[i][0], out[i][0]);
 		out[i][2] = chain_corners(out[i][0], out[i][1]);
 	});
@@ -150,6 +187,24 @@ const TE_RIGHT: EdgeList = [
 	(Edge::DF,0), (Edge::BR,0), (Edge::DB,0), (Edge::DL,0),
 	(Edge::DR,0), (Edge::UR,0), (Edge::BL,0), (Edge::FL,0),
 ];
+#[rustfmt::skip]
+const TE_MIDDLE: EdgeList = [
+	(Edge::UB,1), (Edge::UR,0), (Edge::DB,1), (Edge::UL,0),
+	(Edge::UF,1), (Edge::DR,0), (Edge::DF,1), (Edge::DL,0),
+	(Edge::FR,0), (Edge::BR,0), (Edge::BL,0), (Edge::FL,0),
+];
+#[rustfmt::skip]
+const TE_EQUATOR: EdgeList = [
+	(Edge::UF,0), (Edge::UR,0), (Edge::UB,0), (Edge::UL,0),
+	(Edge::DF,0), (Edge::DR,0), (Edge::DB,0), (Edge::DL,0),
+	(Edge::FL,1), (Edge::FR,1), (Edge::BR,1), (Edge::BL,1),
+];
+#[rustfmt::skip]
+const TE_STANDING: EdgeList = [
+	(Edge::UF,0), (Edge::UL,1), (Edge::UB,0), (Edge::DL,1),
+	(Edge::DF,0), (Edge::UR,1), (Edge::DB,0), (Edge::DR,1),
+	(Edge::FR,0), (Edge::BR,0), (Edge::BL,0), (Edge::FL,0),
+];
 
 /// Chain two edge transformations together
 const fn chain_edges(t1: EdgeList, t2: EdgeList) -> EdgeList {
@@ -167,12 +222,32 @@ const fn chain_edges(t1: EdgeList, t2: EdgeList) -> EdgeList {
 
 /// Generate the transformation table for the corners
 const fn generate_edge_transform_table() -> [[EdgeList; NUM_TURNWISES]; NUM_TURNTYPES] {
-	const BASE: [EdgeList; NUM_SIDES] = [TE_UP, TE_DOWN, TE_BACK, TE_FRONT, TE_LEFT, TE_RIGHT];
+	const BASE: [EdgeList; NUM_BASIC_TURNTYPES] = [
+		TE_UP,
+		TE_DOWN,
+		TE_BACK,
+		TE_FRONT,
+		TE_LEFT,
+		TE_RIGHT,
+		TE_MIDDLE,
+		TE_EQUATOR,
+		TE_STANDING,
+	];
 
 	let mut out = [[TE_BASE; NUM_TURNWISES]; NUM_TURNTYPES];
 
-	const_for!(i in 0..NUM_SIDES => {
-		out[i][0] = BASE[i];
+	const_for!(i in 0..NUM_TURNTYPES => {
+		out[i][0] = if i < NUM_BASIC_TURNTYPES {
+			BASE[i]
+		} else {
+			// Wide turns and rotations are made of the basic turns
+			let parts = TurnType::from_repr(i as u8).unwrap().parts();
+			let mut t = TE_BASE;
+			const_for!(k in 0..parts.len() => {
+				t = chain_edges(t, out[parts[k].side as usize][parts[k].wise as usize]);
+			});
+			t
+		};
 		out[i][1] = chain_edges(out[i][0], out[i][0]);
 		out[i][2] = chain_edges(out[i][0], out[i][1]);
 	});
@@ -182,6 +257,74 @@ const fn generate_edge_transform_table() -> [[EdgeList; NUM_TURNWISES]; NUM_TURN
 
 const EDGE_TRANSFORM: [[EdgeList; NUM_TURNWISES]; NUM_TURNTYPES] = generate_edge_transform_table();
 
+// ===== Center Transformations =====
+#[rustfmt::skip]
+const TN_BASE: CenterList = [
+	Side::Up, Side::Down, Side::Back, Side::Front, Side::Left, Side::Right,
+];
+#[rustfmt::skip]
+const TN_MIDDLE: CenterList = [
+	Side::Back, Side::Front, Side::Down, Side::Up, Side::Left, Side::Right,
+];
+#[rustfmt::skip]
+const TN_EQUATOR: CenterList = [
+	Side::Up, Side::Down, Side::Right, Side::Left, Side::Back, Side::Front,
+];
+#[rustfmt::skip]
+const TN_STANDING: CenterList = [
+	Side::Left, Side::Right, Side::Back, Side::Front, Side::Down, Side::Up,
+];
+
+/// Chain two center transformations together
+const fn chain_centers(t1: CenterList, t2: CenterList) -> CenterList {
+	let mut out = TN_BASE;
+
+	const_for!(i in 0..NUM_SIDES => {
+		out[i] = t1[t2[i] as usize];
+	});
+
+	out
+}
+
+/// Generate the transformation table for the centers
+const fn generate_center_transform_table() -> [[CenterList; NUM_TURNWISES]; NUM_TURNTYPES] {
+	// Only the slices (and turns containing them) move the centers
+	const BASE: [CenterList; NUM_BASIC_TURNTYPES] = [
+		TN_BASE,
+		TN_BASE,
+		TN_BASE,
+		TN_BASE,
+		TN_BASE,
+		TN_BASE,
+		TN_MIDDLE,
+		TN_EQUATOR,
+		TN_STANDING,
+	];
+
+	let mut out = [[TN_BASE; NUM_TURNWISES]; NUM_TURNTYPES];
+
+	const_for!(i in 0..NUM_TURNTYPES => {
+		out[i][0] = if i < NUM_BASIC_TURNTYPES {
+			BASE[i]
+		} else {
+			// Wide turns and rotations are made of the basic turns
+			let parts = TurnType::from_repr(i as u8).unwrap().parts();
+			let mut t = TN_BASE;
+			const_for!(k in 0..parts.len() => {
+				t = chain_centers(t, out[parts[k].side as usize][parts[k].wise as usize]);
+			});
+			t
+		};
+		out[i][1] = chain_centers(out[i][0], out[i][0]);
+		out[i][2] = chain_centers(out[i][0], out[i][1]);
+	});
+
+	out
+}
+
+const CENTER_TRANSFORM: [[CenterList; NUM_TURNWISES]; NUM_TURNTYPES] =
+	generate_center_transform_table();
+
 // ===== Symmetry Transformations =====
 
 #[rustfmt::skip]
@@ -230,10 +373,27 @@ const TE_S_LR: EdgeList = [
 	(Edge::FL, 0), (Edge::BL, 0), (Edge::BR, 0), (Edge::FR, 0),
 ];
 
+#[rustfmt::skip]
+const TN_S_URF3: CenterList = [
+	Side::Front, Side::Back, Side::Left, Side::Right, Side::Down, Side::Up,
+];
+#[rustfmt::skip]
+const TN_S_F2: CenterList = [
+	Side::Down, Side::Up, Side::Back, Side::Front, Side::Right, Side::Left,
+];
+#[rustfmt::skip]
+const TN_S_U4: CenterList = [
+	Side::Up, Side::Down, Side::Left, Side::Right, Side::Front, Side::Back,
+];
+#[rustfmt::skip]
+const TN_S_LR: CenterList = [
+	Side::Up, Side::Down, Side::Back, Side::Front, Side::Right, Side::Left,
+];
+
 pub const NUM_SYMMETRIES: usize = 48;
 
-pub const fn generate_symmetries() -> [(CornerList, EdgeList); NUM_SYMMETRIES] {
-	let mut out = [(TC_BASE, TE_BASE); NUM_SYMMETRIES];
+pub const fn generate_symmetries() -> [(CornerList, EdgeList, CenterList); NUM_SYMMETRIES] {
+	let mut out = [(TC_BASE, TE_BASE, TN_BASE); NUM_SYMMETRIES];
 
 	const_for!(x1 in 0..3 => {
 		const_for!(x2 in 0..2 => {
@@ -241,25 +401,30 @@ pub const fn generate_symmetries() -> [(CornerList, EdgeList); NUM_SYMMETRIES] {
 				const_for!(x4 in 0..2 => {
 					let mut tc = TC_BASE;
 					let mut te = TE_BASE;
+					let mut tn = TN_BASE;
 					const_for!(_ in 0..x1 => {
 						tc = chain_corners(tc, TC_S_URF3);
 						te = chain_edges(te, TE_S_URF3);
+						tn = chain_centers(tn, TN_S_URF3);
 					});
 					const_for!(_ in 0..x2 => {
 						tc = chain_corners(tc, TC_S_F2);
 						te = chain_edges(te, TE_S_F2);
+						tn = chain_centers(tn, TN_S_F2);
 					});
 					const_for!(_ in 0..x3 => {
 						tc = chain_corners(tc, TC_S_U4);
 						te = chain_edges(te, TE_S_U4);
+						tn = chain_centers(tn, TN_S_U4);
 					});
 					const_for!(_ in 0..x4 => {
 						tc = chain_corners(tc, TC_S_LR);
 						te = chain_edges(te, TE_S_LR);
+						tn = chain_centers(tn, TN_S_LR);
 					});
 
 					let idx = 16*x1 + 8*x2 + 2*x3 + x4;
-					out[idx] = (tc,te);
+					out[idx] = (tc,te,tn);
 				});
 			});
 
@@ -269,7 +434,7 @@ pub const fn generate_symmetries() -> [(CornerList, EdgeList); NUM_SYMMETRIES] {
 	out
 }
 
-const SYMMETRIES: [(CornerList, EdgeList); NUM_SYMMETRIES] = generate_symmetries();
+const SYMMETRIES: [(CornerList, EdgeList, CenterList); NUM_SYMMETRIES] = generate_symmetries();
 
 const fn is_c_base(c: CornerList) -> bool {
 	const_for!(i in 0..NUM_CORNERS => {
@@ -294,8 +459,8 @@ const fn generate_symmetry_inverse_list() -> [usize; NUM_SYMMETRIES] {
 
 	const_for!(i in 0..NUM_SYMMETRIES => {
 		const_for!(j in 0.. NUM_SYMMETRIES => {
-			let (tc1, te1) = SYMMETRIES[i];
-			let (tc2, te2) = SYMMETRIES[j];
+			let (tc1, te1, _) = SYMMETRIES[i];
+			let (tc2, te2, _) = SYMMETRIES[j];
 
 			let r1 = is_c_base( chain_corners(tc1, tc2) );
 			let r2 = is_e_base( chain_edges(te1, te2) );
@@ -316,16 +481,19 @@ pub fn get_symmetry(cube: &CubieCube, sym: usize) -> CubieCube {
 	let inv = SYMMETRY_INVERSE[sym];
 	let c = cube.corners;
 	let e = cube.edges;
+	let n = cube.centers;
 
-	let (tc, te) = SYMMETRIES[sym];
-	let (tci, tei) = SYMMETRIES[inv];
+	let (tc, te, tn) = SYMMETRIES[sym];
+	let (tci, tei, tni) = SYMMETRIES[inv];
 
 	let c_res = chain_corners(tc, chain_corners(c, tci));
 	let e_res = chain_edges(te, chain_edges(e, tei));
+	let n_res = chain_centers(tn, chain_centers(n, tni));
 
 	CubieCube {
 		corners: c_res,
 		edges: e_res,
+		centers: n_res,
 	}
 }
 
@@ -356,6 +524,7 @@ impl CubieCube {
 		CubieCube {
 			corners: TC_BASE,
 			edges: TE_BASE,
+			centers: TN_BASE,
 		}
 	}
 
@@ -369,6 +538,11 @@ impl CubieCube {
 		self.edges[e as usize]
 	}
 
+	/// Get the center at the side 's'
+	pub const fn center_at(&self, s: Side) -> Side {
+		self.centers[s as usize]
+	}
+
 	// ===== Coordinates functions =====
 
 	/// Set the corner orientation according to the given coordinate
@@ -494,14 +668,142 @@ impl CubieCube {
 		self.edges = chain_edges(self.edges, te);
 	}
 
+	// ===== Group operations =====
+
+	/// Return the cube, which results from applying the permutation of other to this cube.
+	/// E.g. if the cubes are the results of the sequences A and B, it's the result of A B.
+	pub fn compose(&self, other: &CubieCube) -> CubieCube {
+		CubieCube {
+			corners: chain_corners(self.corners, other.corners),
+			edges: chain_edges(self.edges, other.edges),
+			centers: chain_centers(self.centers, other.centers),
+		}
+	}
+
+	/// Return the cube with the inverse permutation, which undoes this cube:
+	/// `cube.compose(&cube.inverse())` is solved.
+	pub fn inverse(&self) -> CubieCube {
+		let mut out = CubieCube::new();
+		for (i, (c, o)) in self.corners.iter().enumerate() {
+			out.corners[*c as usize] = (Corner::from_repr(i).unwrap(), (3 - o) % 3);
+		}
+		for (i, (e, o)) in self.edges.iter().enumerate() {
+			out.edges[*e as usize] = (Edge::from_repr(i).unwrap(), *o);
+		}
+		for (i, s) in self.centers.iter().enumerate() {
+			out.centers[*s as usize] = Side::from_repr(i as u8).unwrap();
+		}
+		out
+	}
+
+	/// Return the cube after applying it n times
+	pub fn pow(&self, n: u32) -> CubieCube {
+		// Square and multiply
+		let mut out = CubieCube::new();
+		let mut base = self.clone();
+		let mut n = n;
+		while n != 0 {
+			if n & 1 == 1 {
+				out = out.compose(&base);
+			}
+			base = base.compose(&base);
+			n >>= 1;
+		}
+		out
+	}
+
+	/// Return how often the cube must be applied, until it's solved again
+	pub fn order(&self) -> usize {
+		let corners = self.corner_cycles().into_iter().map(|c| c.order(3));
+		let edges = self.edge_cycles().into_iter().map(|c| c.order(2));
+		let centers = self.center_cycles().into_iter().map(|c| c.len());
+		corners.chain(edges).chain(centers).fold(1, lcm)
+	}
+
+	/// Return the cycles of the corners, which are moved or twisted
+	pub fn corner_cycles(&self) -> Vec<Cycle<Corner>> {
+		let pieces: Vec<_> = self
+			.corners
+			.iter()
+			.map(|(c, o)| (*c as usize, *o))
+			.collect();
+		cycles(&pieces, 3)
+			.into_iter()
+			.map(|(positions, orientation)| Cycle {
+				positions: positions
+					.into_iter()
+					.map(|i| Corner::from_repr(i).unwrap())
+					.collect(),
+				orientation,
+			})
+			.collect()
+	}
+
+	/// Return the cycles of the edges, which are moved or flipped
+	pub fn edge_cycles(&self) -> Vec<Cycle<Edge>> {
+		let pieces: Vec<_> = self.edges.iter().map(|(e, o)| (*e as usize, *o)).collect();
+		cycles(&pieces, 2)
+			.into_iter()
+			.map(|(positions, orientation)| Cycle {
+				positions: positions
+					.into_iter()
+					.map(|i| Edge::from_repr(i).unwrap())
+					.collect(),
+				orientation,
+			})
+			.collect()
+	}
+
+	/// Return the cycles of the centers, which are moved
+	pub fn center_cycles(&self) -> Vec<Vec<Side>> {
+		let pieces: Vec<_> = self.centers.iter().map(|s| (*s as usize, 0)).collect();
+		cycles(&pieces, 1)
+			.into_iter()
+			.map(|(positions, _)| {
+				positions
+					.into_iter()
+					.map(|i| Side::from_repr(i as u8).unwrap())
+					.collect()
+			})
+			.collect()
+	}
+
+	/// Return the representative of the cube among all its symmetric cubes
+	/// (`get_symmetry(self, s)` for all 48 symmetries).
+	/// Two cubes are the same up to symmetry, if they have the same representative.
+	pub fn canonical(&self) -> CubieCube {
+		(0..NUM_SYMMETRIES)
+			.map(|s| get_symmetry(self, s))
+			.min_by_key(|cube| {
+				(
+					cube.get_corner_perm_coord(),
+					cube.get_edge_permutation_coord(),
+					cube.get_corner_orientation_coord(),
+					cube.get_edge_orientation_coord(),
+					cube.centers.map(|s| s as u8),
+				)
+			})
+			.unwrap()
+	}
+
+	/// Return true if the cubes are the same up to symmetry
+	pub fn is_equivalent(&self, other: &CubieCube) -> bool {
+		self.canonical() == other.canonical()
+	}
+
 	/// Return true if the cube is solved
 	pub fn is_solved(&self) -> bool {
-		self.edges == TE_BASE && self.corners == TC_BASE
+		self.edges == TE_BASE && self.corners == TC_BASE && self.centers == TN_BASE
 	}
 
 	/// Check the solvability of the cube and return an error type containing
 	/// the cause of the impossibility if it's not solvable
 	pub fn check_solvability(&self) -> Result<(), CubeError> {
+		// The solvers only turn the outer sides, which never move the centers
+		if self.centers != TN_BASE {
+			return Err(CubeError::Centers);
+		}
+
 		// The sum of the corner orientations have to be divisible by 3
 		let cori = self.corners.iter().map(|(_, o)| o).sum::<Ori>();
 		if cori % 3 != 0 {
@@ -539,7 +841,7 @@ impl CubieCube {
 		let e_inv = count_permutation_inversions(eperm);
 
 		// There must be an even number of swaps throughout the permutations
-		if (e_inv + c_inv) % 2 != 0 {
+		if !(e_inv + c_inv).is_multiple_of(2) {
 			return Err(CubeError::Permutation);
 		}
 
@@ -557,7 +859,56 @@ impl RubiksCube for CubieCube {
 		let tc = CORNER_TRANSFORM[turn.side as usize][turn.wise as usize];
 		let te = EDGE_TRANSFORM[turn.side as usize][turn.wise as usize];
 		self.apply_transformation(tc, te);
+
+		if !turn.side.is_side() {
+			let tn = CENTER_TRANSFORM[turn.side as usize][turn.wise as usize];
+			self.centers = chain_centers(self.centers, tn);
+		}
+	}
+}
+
+impl std::ops::Mul for CubieCube {
+	type Output = Self;
+
+	fn mul(self, rhs: Self) -> Self {
+		self.compose(&rhs)
+	}
+}
+
+/// Return the cycles of a permutation with orientations, where pieces\[i\] = (j, o) means
+/// the piece from position j is at position i with the orientation o.
+/// Each cycle is returned with the sum of its orientations (modulo num_orientations),
+/// pieces which are solved are left out.
+fn cycles(pieces: &[(usize, Ori)], num_orientations: Ori) -> Vec<(Vec<usize>, Ori)> {
+	let mut out = vec![];
+	let mut visited = vec![false; pieces.len()];
+
+	for start in 0..pieces.len() {
+		if visited[start] {
+			continue;
+		}
+
+		// Follow the piece at the start until it's back
+		let mut positions = vec![];
+		let mut orientation = 0;
+		let mut pos = start;
+		loop {
+			visited[pos] = true;
+			positions.push(pos);
+			pos = pieces.iter().position(|(j, _)| *j == pos).unwrap();
+			orientation += pieces[pos].1;
+			if pos == start {
+				break;
+			}
+		}
+
+		let orientation = orientation % num_orientations;
+		if positions.len() > 1 || orientation != 0 {
+			out.push((positions, orientation));
+		}
 	}
+
+	out
 }
 
 impl TryFrom<arraycube::ArrayCube> for CubieCube {
@@ -582,6 +933,10 @@ impl TryFrom<arraycube::ArrayCube> for CubieCube {
 			out.corners[corner as usize] = (c, o as Ori);
 		}
 
+		for side in Side::iter() {
+			out.centers[side as usize] = value.color_at(side as usize * CUBE_AREA + 4);
+		}
+
 		Ok(out)
 	}
 }
@@ -616,6 +971,11 @@ impl From<CubieCube> for arraycube::ArrayCube {
 			}
 		}
 
+		for side in Side::iter() {
+			out.data[side as usize * CUBE_AREA + 4] =
+				val.center_at(side) as u8 * CUBE_AREA as u8 + 4;
+		}
+
 		out
 	}
 }
@@ -623,6 +983,8 @@ impl From<CubieCube> for arraycube::ArrayCube {
 #[cfg(test)]
 mod tests {
 	use arraycube::ArrayCube;
+	use notation::parse_turns;
+	use sequence::Sequence;
 
 	use super::*;
 
@@ -631,7 +993,7 @@ mod tests {
 	fn cubiecube_turns1() {
 		let mut cube = CubieCube::new();
 
-		let turns = parse_turns("U D B F L R").unwrap();
+		let turns = parse_turns("U D B F L R M E S").unwrap();
 
 		for turn in turns {
 			for _ in 0..4 {
@@ -672,10 +1034,31 @@ mod tests {
 		}
 	}
 
+	#[test]
+	/// Check that slice turns move the same pieces in arraycube and cubiecube
+	fn arraycube_cubiecube_slices() {
+		let turns = parse_turns("M2 U M' E S2 R E' S F' M").unwrap();
+
+		let mut array = ArrayCube::new();
+		let mut cubie = CubieCube::new();
+
+		for turn in turns {
+			array.apply_turn(turn);
+			cubie.apply_turn(turn);
+
+			let acubie: ArrayCube = cubie.clone().into();
+			let carray: CubieCube = array.clone().try_into().unwrap();
+			assert_eq!(String::from(acubie), String::from(array.clone()));
+			assert_eq!(carray, cubie);
+		}
+
+		assert!(matches!(cubie.check_solvability(), Err(CubeError::Centers)));
+	}
+
 	#[test]
 	/// Check that symmetries are the same in arraycube and cubiecube
 	fn array_cubiecube_symmetries() {
-		let turns = parse_turns("L B R2 U D' R D2 L U' R' B2").unwrap();
+		let turns = parse_turns("L B R2 U D' R D2 L U' R' B2 M E S'").unwrap();
 
 		let mut array = ArrayCube::new();
 		let mut cubie = CubieCube::new();
@@ -700,4 +1083,54 @@ mod tests {
 			}
 		}
 	}
+
+	#[test]
+	/// Check the group operations against sequences
+	fn group_operations() {
+		let cube = |s: &str| {
+			let mut cube = CubieCube::new();
+			for turn in parse_turns(s).unwrap() {
+				cube.apply_turn(turn);
+			}
+			cube
+		};
+
+		let a = cube("R U F' M");
+		let b = cube("L2 D B' E");
+		assert_eq!(a.compose(&b), cube("R U F' M L2 D B' E"));
+		assert_eq!(a.clone() * b, cube("R U F' M L2 D B' E"));
+		assert_eq!(a.inverse(), cube("M' F U' R'"));
+		assert_eq!(a.pow(5), cube("(R U F' M)5"));
+
+		assert_eq!(cube("R").order(), 4);
+		assert_eq!(cube("R U").order(), 105);
+		assert_eq!(cube("R U R' U'").order(), 6);
+		assert_eq!(cube("M2 E2 S2").order(), 2);
+		assert!(cube("R U").pow(105).is_solved());
+
+		// The T-perm swaps two corners and two edges
+		let t_perm = cube("R U R' U' R' F R2 U' R' U' R U R' F'");
+		assert_eq!(
+			t_perm.corner_cycles(),
+			vec![Cycle {
+				positions: vec![Corner::URF, Corner::UBR],
+				orientation: 0
+			}]
+		);
+		assert_eq!(
+			t_perm.edge_cycles(),
+			vec![Cycle {
+				positions: vec![Edge::UR, Edge::UL],
+				orientation: 0
+			}]
+		);
+
+		// A sequence seen through a symmetry results in an equivalent cube
+		let seq: Sequence = "R U2 F' D".parse().unwrap();
+		let a = cube(&seq.to_string());
+		for s in 0..NUM_SYMMETRIES {
+			assert!(a.is_equivalent(&cube(&seq.rotate(s).to_string())));
+		}
+		assert!(!a.is_equivalent(&cube("R U2 F' D'")));
+	}
 }
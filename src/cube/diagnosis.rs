@@ -0,0 +1,539 @@
+use strum::IntoEnumIterator;
+
+use crate::math::permutation_parity;
+
+use super::{
+	arraycube::{corner_to_indices, edge_to_indices, ArrayCube, FromStrError, CUBEDATA_LEN},
+	cubiecube::CubieCube,
+	placement::{Cost, Placements, Weight},
+	turn::{Turn, TurnType, TurnWise},
+	Corner, Edge, RubiksCube, Side, CUBE_AREA, CUBE_DIM, NUM_CORNERS, NUM_EDGES, NUM_SIDES,
+};
+
+/// Join the items with spaces
+fn join<T: std::fmt::Display>(items: &[T]) -> String {
+	items
+		.iter()
+		.map(|item| item.to_string())
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// A reason why entered stickers don't form a solvable cube
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+	#[error(
+		"The color of the {color:?} side appears {count} times instead of {}",
+		CUBE_AREA
+	)]
+	ColorCount { color: Side, count: usize },
+	#[error("The center of the {side:?} side has the color of the {color:?} side")]
+	Center { side: Side, color: Side },
+	#[error("The corner at {position} has an impossible color combination")]
+	InvalidCorner { position: Corner },
+	#[error("The colors of the corner {corner} at {position} are in mirrored order")]
+	MirroredCorner { position: Corner, corner: Corner },
+	#[error("The edge at {position} has an impossible color combination")]
+	InvalidEdge { position: Edge },
+	#[error("The corner {corner} appears {} times (at {})", .positions.len(), join(.positions))]
+	DuplicateCorner {
+		corner: Corner,
+		positions: Vec<Corner>,
+	},
+	#[error("The corner {corner} is missing")]
+	MissingCorner { corner: Corner },
+	#[error("The edge {edge} appears {} times (at {})", .positions.len(), join(.positions))]
+	DuplicateEdge { edge: Edge, positions: Vec<Edge> },
+	#[error("The edge {edge} is missing")]
+	MissingEdge { edge: Edge },
+	#[error("The corners are twisted by +{twist} in total (twisted corners: {})", join(.twisted))]
+	CornerTwist { twist: usize, twisted: Vec<Corner> },
+	#[error("An odd number of edges is flipped (flipped edges: {})", join(.flipped))]
+	EdgeFlip { flipped: Vec<Edge> },
+	#[error("The corners and edges need an odd number of swaps")]
+	Parity,
+}
+
+/// A sticker, which has to change its color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StickerFix {
+	/// The index of the sticker in the cube string
+	pub index: usize,
+	/// The color it has
+	pub from: Side,
+	/// The color it should have
+	pub to: Side,
+}
+
+impl std::fmt::Display for StickerFix {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let side = Side::from_repr((self.index / CUBE_AREA) as u8).unwrap();
+		let i = self.index % CUBE_AREA;
+		write!(
+			f,
+			"{:?} side, row {}, column {}: {:?} -> {:?}",
+			side,
+			i / CUBE_DIM + 1,
+			i % CUBE_DIM + 1,
+			self.from,
+			self.to
+		)
+	}
+}
+
+/// Everything which keeps the stickers from forming a solvable cube,
+/// and the fewest sticker changes which make it solvable.
+///
+/// If the centers have different colors, the corners and edges are checked against the
+/// colors of the centers, even if they are moved (e.g. by a slice turn). The centers are
+/// then never changed, and the rotation which puts them back on their sides is given.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnosis {
+	pub problems: Vec<Problem>,
+	/// The stickers to change (sorted by their index)
+	pub fixes: Vec<StickerFix>,
+	/// The rotation of the whole cube, which puts moved centers back on their sides
+	pub rotation: Option<Vec<Turn>>,
+}
+
+/// Return the stickers (index and color) of the corner at the position with the orientation
+pub(crate) fn corner_stickers(pos: Corner, corner: Corner, ori: usize) -> [(usize, Side); 3] {
+	let indices: [usize; 3] = corner_to_indices(pos).into();
+	let cols: [usize; 3] = corner_to_indices(corner).into();
+	std::array::from_fn(|i| (indices[i], side_of(cols[(3 - ori + i) % 3])))
+}
+
+/// Return the stickers (index and color) of the edge at the position with the orientation
+pub(crate) fn edge_stickers(pos: Edge, edge: Edge, ori: usize) -> [(usize, Side); 2] {
+	let indices: [usize; 2] = edge_to_indices(pos).into();
+	let cols: [usize; 2] = edge_to_indices(edge).into();
+	std::array::from_fn(|i| (indices[i], side_of(cols[(ori + i) % 2])))
+}
+
+/// Return the side of a sticker index
+fn side_of(index: usize) -> Side {
+	Side::from_repr((index / CUBE_AREA) as u8).unwrap()
+}
+
+impl Diagnosis {
+	/// Diagnose the stickers of a cube string in the native format
+	pub fn from_native(s: &str) -> Result<Self, FromStrError> {
+		let chars: Vec<char> = s.chars().collect();
+		if chars.len() != CUBEDATA_LEN {
+			return Err(FromStrError::Length);
+		}
+
+		let mut colors = vec![];
+		for (i, c) in chars.into_iter().enumerate() {
+			match ('a'..='f').contains(&c) {
+				true => colors.push(Side::from_repr(c as u8 - b'a').unwrap()),
+				false => {
+					return Err(FromStrError::Color {
+						color: c,
+						position: i + 1,
+					})
+				}
+			}
+		}
+
+		Ok(Self::new(&colors))
+	}
+
+	/// Diagnose the given sticker colors (in the order of the cube string)
+	pub fn new(colors: &[Side]) -> Self {
+		debug_assert_eq!(colors.len(), CUBEDATA_LEN);
+
+		// The colors relative to the centers, which are fixed
+		let sides = center_sides(colors);
+		let relative: Vec<Side> = match sides {
+			Some(sides) => colors.iter().map(|c| sides[*c as usize]).collect(),
+			None => colors.to_vec(),
+		};
+		let center = |side: Side| colors[side as usize * CUBE_AREA + 4];
+
+		let mut fixes = fixes(&relative);
+		if sides.is_some() {
+			for fix in fixes.iter_mut() {
+				fix.to = center(fix.to);
+			}
+		}
+
+		Self {
+			problems: problems(colors, &relative),
+			fixes,
+			rotation: sides
+				.filter(|_| Side::iter().any(|side| center(side) != side))
+				.and_then(|_| rotation(colors)),
+		}
+	}
+
+	/// Return true if the stickers form a solvable cube
+	pub fn is_solvable(&self) -> bool {
+		self.problems.is_empty()
+	}
+}
+
+impl From<&ArrayCube> for Diagnosis {
+	fn from(cube: &ArrayCube) -> Self {
+		let colors: Vec<Side> = (0..CUBEDATA_LEN).map(|i| cube.color_at(i)).collect();
+		Self::new(&colors)
+	}
+}
+
+/// Return the side of the center of each color, if all centers have different colors
+fn center_sides(colors: &[Side]) -> Option<[Side; NUM_SIDES]> {
+	let mut sides = [None; NUM_SIDES];
+	for side in Side::iter() {
+		let color = colors[side as usize * CUBE_AREA + 4] as usize;
+		if sides[color].replace(side).is_some() {
+			return None;
+		}
+	}
+	Some(sides.map(|side| side.unwrap()))
+}
+
+/// Return the shortest rotation of the whole cube, which puts the centers on their sides
+fn rotation(colors: &[Side]) -> Option<Vec<Turn>> {
+	let rotations: Vec<Turn> = [TurnType::X, TurnType::Y, TurnType::Z]
+		.into_iter()
+		.flat_map(|side| TurnWise::iter().map(move |wise| Turn { side, wise }))
+		.collect();
+
+	// Every rotation is one or two of these
+	std::iter::once(vec![])
+		.chain(rotations.iter().map(|r| vec![*r]))
+		.chain(
+			rotations
+				.iter()
+				.flat_map(|a| rotations.iter().map(|b| vec![*a, *b])),
+		)
+		.find(|turns| {
+			// The frame tells, which center is moved to each side
+			let mut frame = CubieCube::new();
+			for turn in turns {
+				frame.apply_turn(*turn);
+			}
+			Side::iter().all(|side| colors[frame.center_at(side) as usize * CUBE_AREA + 4] == side)
+		})
+}
+
+/// Return all problems of the stickers.
+/// The corners and edges are checked with the colors relative to the centers.
+fn problems(colors: &[Side], relative: &[Side]) -> Vec<Problem> {
+	let mut problems = vec![];
+
+	for color in Side::iter() {
+		let count = colors.iter().filter(|c| **c == color).count();
+		if count != CUBE_AREA {
+			problems.push(Problem::ColorCount { color, count });
+		}
+	}
+
+	for side in Side::iter() {
+		let color = colors[side as usize * CUBE_AREA + 4];
+		if color != side {
+			problems.push(Problem::Center { side, color });
+		}
+	}
+
+	let colors = relative;
+	let center_problems = problems.len();
+	let cube = ArrayCube {
+		data: std::array::from_fn(|i| colors[i] as u8 * CUBE_AREA as u8),
+	};
+
+	// The positions of each piece and the pieces with their orientation
+	let mut corner_positions = vec![vec![]; NUM_CORNERS];
+	let mut corners = vec![];
+	for pos in Corner::iter() {
+		match cube.get_corner_at_pos(pos) {
+			None => problems.push(Problem::InvalidCorner { position: pos }),
+			Some((corner, ori)) => {
+				let mirrored = corner_stickers(pos, corner, ori)
+					.iter()
+					.any(|(i, color)| colors[*i] != *color);
+				if mirrored {
+					problems.push(Problem::MirroredCorner {
+						position: pos,
+						corner,
+					});
+				}
+				corner_positions[corner as usize].push(pos);
+				corners.push((corner, ori));
+			}
+		}
+	}
+
+	let mut edge_positions = vec![vec![]; NUM_EDGES];
+	let mut edges = vec![];
+	for pos in Edge::iter() {
+		match cube.get_edge_at_pos(pos) {
+			None => problems.push(Problem::InvalidEdge { position: pos }),
+			Some((edge, ori)) => {
+				edge_positions[edge as usize].push(pos);
+				edges.push((edge, ori));
+			}
+		}
+	}
+
+	for corner in Corner::iter() {
+		let positions = &corner_positions[corner as usize];
+		match positions.len() {
+			0 => problems.push(Problem::MissingCorner { corner }),
+			1 => {}
+			_ => problems.push(Problem::DuplicateCorner {
+				corner,
+				positions: positions.clone(),
+			}),
+		}
+	}
+
+	for edge in Edge::iter() {
+		let positions = &edge_positions[edge as usize];
+		match positions.len() {
+			0 => problems.push(Problem::MissingEdge { edge }),
+			1 => {}
+			_ => problems.push(Problem::DuplicateEdge {
+				edge,
+				positions: positions.clone(),
+			}),
+		}
+	}
+
+	// The parities are only meaningful, if every piece is there once
+	if problems.len() > center_problems {
+		return problems;
+	}
+
+	let twist = corners.iter().map(|(_, o)| o).sum::<usize>() % 3;
+	if twist != 0 {
+		let twisted = Corner::iter()
+			.zip(corners.iter())
+			.filter(|(_, (_, o))| *o != 0)
+			.map(|(pos, _)| pos)
+			.collect();
+		problems.push(Problem::CornerTwist { twist, twisted });
+	}
+
+	let flipped: Vec<Edge> = Edge::iter()
+		.zip(edges.iter())
+		.filter(|(_, (_, o))| *o != 0)
+		.map(|(pos, _)| pos)
+		.collect();
+	if !flipped.len().is_multiple_of(2) {
+		problems.push(Problem::EdgeFlip { flipped });
+	}
+
+	let cperm: Vec<usize> = corners.iter().map(|(c, _)| *c as usize).collect();
+	let eperm: Vec<usize> = edges.iter().map(|(e, _)| *e as usize).collect();
+	if permutation_parity(&cperm) != permutation_parity(&eperm) {
+		problems.push(Problem::Parity);
+	}
+
+	problems
+}
+
+/// Return the fewest sticker changes, which make the stickers a solvable cube
+fn fixes(colors: &[Side]) -> Vec<StickerFix> {
+	// The number of wrong stickers for every piece and orientation at every position
+	let corner_cost = Corner::iter()
+		.map(|pos| {
+			Corner::iter()
+				.flat_map(|corner| (0..3).map(move |ori| (corner, ori)))
+				.map(|(corner, ori)| {
+					let stickers = corner_stickers(pos, corner, ori);
+					let cost = stickers.iter().filter(|(i, c)| colors[*i] != *c).count();
+					(corner as usize, ori, Cost(cost))
+				})
+				.collect()
+		})
+		.collect();
+	let edge_cost = Edge::iter()
+		.map(|pos| {
+			Edge::iter()
+				.flat_map(|edge| (0..2).map(move |ori| (edge, ori)))
+				.map(|(edge, ori)| {
+					let stickers = edge_stickers(pos, edge, ori);
+					let cost = stickers.iter().filter(|(i, c)| colors[*i] != *c).count();
+					(edge as usize, ori, Cost(cost))
+				})
+				.collect()
+		})
+		.collect();
+
+	// The corners and edges must have the same parity
+	let corners = Placements::new(corner_cost, 3);
+	let edges = Placements::new(edge_cost, 2);
+	let parity = (0..2)
+		.min_by_key(|parity| corners.total(*parity).extend(edges.total(*parity)))
+		.unwrap();
+	let corners = corners.best(parity).unwrap();
+	let edges = edges.best(parity).unwrap();
+
+	// The colors of the closest solvable cube
+	let mut target = colors.to_vec();
+	for side in Side::iter() {
+		target[side as usize * CUBE_AREA + 4] = side;
+	}
+	for (pos, (corner, ori)) in Corner::iter().zip(corners) {
+		for (i, color) in corner_stickers(pos, Corner::from_repr(corner).unwrap(), ori) {
+			target[i] = color;
+		}
+	}
+	for (pos, (edge, ori)) in Edge::iter().zip(edges) {
+		for (i, color) in edge_stickers(pos, Edge::from_repr(edge).unwrap(), ori) {
+			target[i] = color;
+		}
+	}
+
+	(0..CUBEDATA_LEN)
+		.filter(|i| colors[*i] != target[*i])
+		.map(|i| StickerFix {
+			index: i,
+			from: colors[i],
+			to: target[i],
+		})
+		.collect()
+}
+
+impl std::fmt::Display for Diagnosis {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if self.is_solvable() {
+			return write!(f, "The cube is solvable");
+		}
+
+		for (i, problem) in self.problems.iter().enumerate() {
+			if i > 0 {
+				writeln!(f)?;
+			}
+			write!(f, "{}", problem)?;
+		}
+		if !self.fixes.is_empty() {
+			write!(f, "\nChange these stickers to make the cube solvable:")?;
+			for fix in self.fixes.iter() {
+				write!(f, "\n  {}", fix)?;
+			}
+		}
+		if let Some(rotation) = &self.rotation {
+			write!(
+				f,
+				"\nRotate the cube with {} to put the centers on their sides",
+				join(rotation)
+			)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use rand::{rngs::StdRng, Rng, SeedableRng};
+
+	use super::*;
+	use crate::cube::{cubiecube::CubieCube, turn::random_sequence};
+
+	/// Return the sticker colors of the cube
+	fn colors(cube: &ArrayCube) -> Vec<Side> {
+		(0..CUBEDATA_LEN).map(|i| cube.color_at(i)).collect()
+	}
+
+	/// Return true if the colors form a solvable cube
+	fn is_solvable(colors: &[Side]) -> bool {
+		let s: String = colors.iter().map(|c| (b'a' + *c as u8) as char).collect();
+		ArrayCube::from_str(&s)
+			.ok()
+			.and_then(|cube| CubieCube::try_from(cube).ok())
+			.is_some_and(|cube| cube.is_solvable())
+	}
+
+	#[test]
+	/// A twisted corner is found and twisted back
+	fn twisted_corner() {
+		let mut colors = colors(&ArrayCube::default());
+		let [a, b, c] = corner_stickers(Corner::URF, Corner::URF, 1);
+		for (i, color) in [a, b, c] {
+			colors[i] = color;
+		}
+
+		let diagnosis = Diagnosis::new(&colors);
+		assert_eq!(
+			diagnosis.problems,
+			vec![Problem::CornerTwist {
+				twist: 1,
+				twisted: vec![Corner::URF]
+			}]
+		);
+		assert_eq!(diagnosis.fixes.len(), 3);
+	}
+
+	#[test]
+	/// Moved centers are put back by a rotation, and the other stickers are fixed
+	/// relative to the centers
+	fn moved_centers() {
+		for (sequence, rotation) in [("M", "x"), ("R E2 F", "y2"), ("z S", "z2")] {
+			let mut cube = ArrayCube::default();
+			cube.apply_turns(crate::parse_turns(sequence).unwrap());
+			let mut colors = colors(&cube);
+
+			let diagnosis = Diagnosis::new(&colors);
+			assert!(!diagnosis.is_solvable());
+			assert!(diagnosis.fixes.is_empty(), "{sequence}");
+			assert_eq!(
+				diagnosis.rotation,
+				Some(crate::parse_turns(rotation).unwrap()),
+				"{sequence}"
+			);
+
+			// A twisted corner is fixed without changing the centers
+			let (a, b, c) = corner_to_indices(Corner::URF);
+			(colors[a], colors[b], colors[c]) = (colors[c], colors[a], colors[b]);
+
+			let diagnosis = Diagnosis::new(&colors);
+			assert_eq!(diagnosis.fixes.len(), 3, "{sequence}");
+			for fix in diagnosis.fixes {
+				assert_ne!(fix.index % CUBE_AREA, 4);
+				colors[fix.index] = fix.to;
+			}
+
+			let s: String = colors.iter().map(|c| (b'a' + *c as u8) as char).collect();
+			let mut cube = ArrayCube::from_str(&s).unwrap();
+			cube.apply_turns(crate::parse_turns(rotation).unwrap());
+			assert!(Diagnosis::from(&cube).is_solvable(), "{sequence}");
+		}
+	}
+
+	#[test]
+	/// Applying the fixes of wrong stickers makes the cube solvable
+	fn sticker_fixes() {
+		let mut rng = StdRng::seed_from_u64(42);
+
+		for _ in 0..20 {
+			let mut cube = ArrayCube::default();
+			cube.apply_turns(random_sequence(20));
+			let mut colors = colors(&cube);
+			assert!(Diagnosis::new(&colors).is_solvable());
+			assert!(Diagnosis::new(&colors).fixes.is_empty());
+
+			// Change up to three stickers, which are no centers
+			let wrong = rng.gen_range(1..=3);
+			for _ in 0..wrong {
+				let i = rng.gen_range(0..CUBEDATA_LEN / 2) * 2 + 1;
+				if i % CUBE_AREA == 4 {
+					continue;
+				}
+				colors[i] = Side::from_repr(rng.gen_range(0..NUM_SIDES as u8)).unwrap();
+			}
+
+			let diagnosis = Diagnosis::new(&colors);
+			assert!(diagnosis.fixes.len() <= wrong);
+			assert_eq!(diagnosis.is_solvable(), diagnosis.fixes.is_empty());
+			for fix in diagnosis.fixes {
+				assert_eq!(colors[fix.index], fix.from);
+				colors[fix.index] = fix.to;
+			}
+			assert!(is_solvable(&colors));
+		}
+	}
+}
@@ -0,0 +1,83 @@
+use std::str::FromStr;
+
+use super::{
+	arraycube::{ArrayCube, FromStrError},
+	completion::Completion,
+	diagnosis::Diagnosis,
+};
+
+/// The text formats a cube can be read from and written to
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Default,
+	PartialEq,
+	Eq,
+	Hash,
+	strum::EnumIter,
+	strum::EnumString,
+	strum::Display,
+)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Format {
+	/// The colors a-f of each facelet, with the sides in the order U D B F L R
+	#[default]
+	Native,
+	/// The facelet string of Kociemba's tools and cubing.js:
+	/// The letters URFDLB of each facelet, with the sides in the order U R F D L B
+	Kociemba,
+	/// The sticker colors WYGBRO, with the sides in the order U D B F L R.
+	/// The color scheme is inferred from the centers.
+	Colors,
+}
+
+impl Format {
+	/// Parse a cube from a string in this format
+	pub fn parse(&self, s: &str) -> Result<ArrayCube, FromStrError> {
+		match self {
+			Format::Native => ArrayCube::from_str(s),
+			Format::Kociemba => ArrayCube::from_kociemba(s),
+			Format::Colors => ArrayCube::from_colors(s),
+		}
+	}
+
+	/// Read a cube string in this format with unknown stickers ('?')
+	pub fn complete(&self, s: &str) -> Result<Completion, FromStrError> {
+		Completion::from_native(&self.native(s)?)
+	}
+
+	/// Rewrite a cube string in this format to the native format, without checking the cube
+	fn native(&self, s: &str) -> Result<String, FromStrError> {
+		match self {
+			Format::Native => Ok(s.to_string()),
+			Format::Kociemba => ArrayCube::kociemba_to_native(s),
+			Format::Colors => ArrayCube::colors_to_native(s),
+		}
+	}
+
+	/// Find out what's wrong with the stickers of a cube string in this format.
+	/// It only fails, if the stickers can't be read at all.
+	pub fn diagnose(&self, s: &str) -> Result<Diagnosis, FromStrError> {
+		Diagnosis::from_native(&self.native(s)?)
+	}
+
+	/// Write the cube as a string in this format
+	pub fn format(&self, cube: &ArrayCube) -> String {
+		match self {
+			Format::Native => cube.clone().into(),
+			Format::Kociemba => cube.to_kociemba(),
+			Format::Colors => cube.to_colors(),
+		}
+	}
+
+	/// Write the stickers of a completion in this format, with '?' for unknown ones
+	pub fn format_completion(&self, completion: &Completion) -> String {
+		let native = completion.to_native();
+		match self {
+			Format::Native => native,
+			Format::Kociemba => ArrayCube::native_to_kociemba(&native),
+			Format::Colors => ArrayCube::native_to_colors(&native),
+		}
+	}
+}
@@ -1,5 +1,11 @@
 pub mod arraycube;
+pub mod completion;
 pub mod cubiecube;
+pub mod diagnosis;
+pub mod format;
+pub mod notation;
+mod placement;
+pub mod sequence;
 pub mod turn;
 
 use strum::EnumCount;
@@ -15,7 +21,18 @@ pub const CUBE_AREA: usize = CUBE_DIM * CUBE_DIM;
 pub const NUM_SIDES: usize = 6;
 
 // TODO: Change this to an enum
-#[derive(Eq, PartialEq, PartialOrd, Copy, Clone, strum::EnumCount, strum::FromRepr)]
+#[derive(
+	Eq,
+	PartialEq,
+	PartialOrd,
+	Copy,
+	Clone,
+	Debug,
+	strum::EnumIter,
+	strum::EnumCount,
+	strum::FromRepr,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Side {
 	// It must only contain unit fields!
@@ -42,7 +59,8 @@ pub fn get_ansii_color(side: Side) -> &'static str {
 // ===== Edge Piece =====
 
 /// All the different position names for an Edge
-#[derive(Clone, Copy, Default, PartialEq, Eq, strum::EnumIter, strum::EnumCount, strum::Display, Debug)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, strum::EnumIter, strum::EnumCount, strum::Display, strum::FromRepr, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(usize)]
 #[rustfmt::skip]
 pub enum Edge {
@@ -105,8 +123,10 @@ impl Edge {
 /// A corner piece
 /// Note that the name is carefully sorted!
 #[derive(
-	Clone, Copy, Default, PartialEq, Eq, Debug, strum::EnumIter, strum::EnumString, strum::EnumCount, strum::Display
+	Clone, Copy, Default, PartialEq, Eq, Debug, strum::EnumIter, strum::EnumString, strum::EnumCount, strum::Display,
+	strum::FromRepr,
 )]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(clippy::upper_case_acronyms)]
 #[repr(usize)]
 #[rustfmt::skip]
@@ -181,6 +201,8 @@ pub enum CubeError {
 	Permutation,
 	#[error("Not all cubies are present on the cube")]
 	Cubies,
+	#[error("The centers are not in their home position")]
+	Centers,
 }
 
 /// The RubiksCube trait.
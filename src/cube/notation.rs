@@ -0,0 +1,333 @@
+use std::str::FromStr;
+
+use super::{sequence::Sequence, turn::*};
+
+/// The error returned when a turn or a sequence couldn't be parsed
+/// Columns start at 1 and count the characters of the whole input.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseTurnError {
+	#[error("'{token}' at column {column} is not a valid turn")]
+	InvalidTurn { token: String, column: usize },
+	#[error("Unexpected '{token}' at column {column}")]
+	Unexpected { token: String, column: usize },
+	#[error("'{token}' at column {column} is never closed")]
+	Unclosed { token: char, column: usize },
+	#[error(
+		"The repetition {token} at column {column} makes the sequence longer than {} turns",
+		MAX_TURNS
+	)]
+	TooLong { token: String, column: usize },
+}
+
+/// The letters a turn can start with
+const TURN_LETTERS: &str = "UDBFLRMESudbflrxyz";
+
+/// The maximum number of turns a repeated group may expand to
+pub const MAX_TURNS: usize = 1_000_000;
+
+/// Return the inverse of a sequence
+fn invert(turns: &[Turn]) -> Vec<Turn> {
+	Sequence::new(turns.to_vec()).inverse().into()
+}
+
+/// A recursive descent parser for sequences of turns
+///
+/// sequence := item*
+/// item     := turn | '(' sequence ')' suffix | '[' sequence (',' | ':') sequence ']' suffix
+/// turn     := letter ['w'] suffix
+/// suffix   := [number] [''']
+///
+/// Whitespace is optional and "//" starts a comment until the end of the line.
+struct Parser {
+	chars: Vec<char>,
+	pos: usize,
+}
+
+impl Parser {
+	fn new(s: &str) -> Self {
+		Self {
+			chars: s.chars().collect(),
+			pos: 0,
+		}
+	}
+
+	/// Return the current column
+	fn column(&self) -> usize {
+		self.pos + 1
+	}
+
+	/// Skip whitespace and comments, then return the next character
+	fn peek(&mut self) -> Option<char> {
+		loop {
+			match self.chars.get(self.pos) {
+				Some(c) if c.is_whitespace() => self.pos += 1,
+				Some('/') if self.chars.get(self.pos + 1) == Some(&'/') => {
+					while self.chars.get(self.pos).is_some_and(|c| *c != '\n') {
+						self.pos += 1;
+					}
+				}
+				c => return c.copied(),
+			}
+		}
+	}
+
+	/// Return an error for the next character
+	fn unexpected(&mut self) -> ParseTurnError {
+		let column = self.column();
+		let token = self.peek().map(String::from).unwrap_or_default();
+		ParseTurnError::Unexpected { token, column }
+	}
+
+	/// Parse items until the end of the input or of the enclosing brackets
+	fn sequence(&mut self) -> Result<Vec<Turn>, ParseTurnError> {
+		let mut out = vec![];
+
+		loop {
+			match self.peek() {
+				None | Some(')' | ']' | ',' | ':') => return Ok(out),
+				Some('(') => out.extend(self.group()?),
+				Some('[') => out.extend(self.bracket()?),
+				Some(_) => out.extend(self.turn()?),
+			}
+		}
+	}
+
+	/// Parse a repetition count and an inversion directly after a turn or a group.
+	/// Return the count modulo 4 (if reduce is set) and whether it's inverted.
+	/// Counts which don't fit into an usize are saturated.
+	fn suffix(&mut self, reduce: bool) -> (usize, bool) {
+		let mut count = None;
+		while let Some(d) = self.chars.get(self.pos).and_then(|c| c.to_digit(10)) {
+			let c = count
+				.unwrap_or(0usize)
+				.saturating_mul(10)
+				.saturating_add(d as usize);
+			count = Some(if reduce { c % 4 } else { c });
+			self.pos += 1;
+		}
+
+		let inverted = self.chars.get(self.pos) == Some(&'\'');
+		if inverted {
+			self.pos += 1;
+		}
+
+		(count.unwrap_or(1), inverted)
+	}
+
+	/// Apply the suffix of a group to its turns
+	fn repeat(&mut self, turns: Vec<Turn>) -> Result<Vec<Turn>, ParseTurnError> {
+		let start = self.pos;
+		let (count, inverted) = self.suffix(false);
+
+		if turns
+			.len()
+			.checked_mul(count)
+			.is_none_or(|len| len > MAX_TURNS)
+		{
+			let token = self.chars[start..self.pos].iter().collect();
+			let column = start + 1;
+			return Err(ParseTurnError::TooLong { token, column });
+		}
+
+		let turns = if inverted { invert(&turns) } else { turns };
+		Ok(turns.repeat(count))
+	}
+
+	/// Parse a group in parentheses
+	fn group(&mut self) -> Result<Vec<Turn>, ParseTurnError> {
+		let column = self.column();
+		self.pos += 1;
+
+		let turns = self.sequence()?;
+		match self.peek() {
+			Some(')') => self.pos += 1,
+			None => return Err(ParseTurnError::Unclosed { token: '(', column }),
+			Some(_) => return Err(self.unexpected()),
+		}
+
+		self.repeat(turns)
+	}
+
+	/// Parse a commutator [A, B] = A B A' B' or a conjugate [A: B] = A B A'
+	fn bracket(&mut self) -> Result<Vec<Turn>, ParseTurnError> {
+		let column = self.column();
+		self.pos += 1;
+
+		let a = self.sequence()?;
+		let separator = match self.peek() {
+			Some(c @ (',' | ':')) => c,
+			None => return Err(ParseTurnError::Unclosed { token: '[', column }),
+			Some(_) => return Err(self.unexpected()),
+		};
+		self.pos += 1;
+
+		let b = self.sequence()?;
+		match self.peek() {
+			Some(']') => self.pos += 1,
+			None => return Err(ParseTurnError::Unclosed { token: '[', column }),
+			Some(_) => return Err(self.unexpected()),
+		}
+
+		let mut turns = [a.clone(), b.clone(), invert(&a)].concat();
+		if separator == ',' {
+			turns.extend(invert(&b));
+		}
+
+		self.repeat(turns)
+	}
+
+	/// Parse a single turn, which may cancel itself (like R4)
+	fn turn(&mut self) -> Result<Option<Turn>, ParseTurnError> {
+		let start = self.pos;
+		let column = self.column();
+
+		match self.chars.get(self.pos) {
+			Some(c) if TURN_LETTERS.contains(*c) => self.pos += 1,
+			Some(c) if c.is_alphabetic() => {
+				let token = c.to_string();
+				return Err(ParseTurnError::InvalidTurn { token, column });
+			}
+			_ => return Err(self.unexpected()),
+		}
+		// Wide turns of the sides
+		if self.chars.get(self.pos) == Some(&'w') && "UDBFLR".contains(self.chars[start]) {
+			self.pos += 1;
+		}
+		let name: String = self.chars[start..self.pos].iter().collect();
+		let (count, inverted) = self.suffix(true);
+
+		let side = match TurnType::from_str(&name) {
+			Ok(side) => side,
+			Err(_) => {
+				let token = self.chars[start..self.pos].iter().collect();
+				return Err(ParseTurnError::InvalidTurn { token, column });
+			}
+		};
+
+		let count = if inverted { (4 - count) % 4 } else { count };
+		let wise = match count {
+			1 => TurnWise::Clockwise,
+			2 => TurnWise::Double,
+			3 => TurnWise::CounterClockwise,
+			_ => return Ok(None),
+		};
+
+		Ok(Some(Turn { side, wise }))
+	}
+}
+
+impl FromStr for Turn {
+	type Err = ParseTurnError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parser = Parser::new(s);
+		match parser.turn() {
+			Ok(Some(turn)) if parser.pos == parser.chars.len() => Ok(turn),
+			_ => Err(ParseTurnError::InvalidTurn {
+				token: s.to_string(),
+				column: 1,
+			}),
+		}
+	}
+}
+
+/// Parse a sequence of turns
+///
+/// Besides single turns (like `R`, `U2`, `Rw'` or `x`), a sequence may contain groups with
+/// repetitions `(R U R' U')3`, commutators `[R, U]` and conjugates `[F: [R, U]]`.
+/// Spaces between turns are optional and `//` starts a comment until the end of the line.
+pub fn parse_turns<T>(item: T) -> Result<Vec<Turn>, ParseTurnError>
+where
+	T: Into<String>,
+{
+	let mut parser = Parser::new(&item.into());
+	let turns = parser.sequence()?;
+
+	match parser.peek() {
+		None => Ok(turns),
+		Some(_) => Err(parser.unexpected()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Parse a sequence and print it again
+	fn normalized(s: &str) -> String {
+		let turns = parse_turns(s).unwrap();
+		let turns: Vec<_> = turns.iter().map(|t| t.to_string()).collect();
+		turns.join(" ")
+	}
+
+	#[test]
+	/// Check the different ways of writing sequences
+	fn sequence_notation() {
+		assert_eq!(normalized("RUR'U'"), "R U R' U'");
+		assert_eq!(normalized("R2' U3 x2 Rw r' R4"), "R2 U' x2 Rw Rw'");
+		assert_eq!(normalized("(R U)2 (R U')'"), "R U R U U R'");
+		assert_eq!(normalized("[R, U]"), "R U R' U'");
+		assert_eq!(normalized("[F: [R, U]]"), "F R U R' U' F'");
+		assert_eq!(normalized("[R, U]2'"), "U R U' R' U R U' R'");
+		assert_eq!(normalized("R U // comment\n  F // R\n"), "R U F");
+		assert_eq!(normalized(""), "");
+	}
+
+	#[test]
+	/// Errors report the column of the offending token
+	fn sequence_errors() {
+		let err = |s: &str| parse_turns(s).unwrap_err();
+
+		assert_eq!(
+			err("R U Q"),
+			ParseTurnError::InvalidTurn {
+				token: "Q".into(),
+				column: 5
+			}
+		);
+		assert_eq!(
+			err("R U }"),
+			ParseTurnError::Unexpected {
+				token: "}".into(),
+				column: 5
+			}
+		);
+		assert_eq!(
+			err("R (U R"),
+			ParseTurnError::Unclosed {
+				token: '(',
+				column: 3
+			}
+		);
+		assert_eq!(
+			err("[R U]"),
+			ParseTurnError::Unexpected {
+				token: "]".into(),
+				column: 5
+			}
+		);
+		assert_eq!(
+			err("R U)"),
+			ParseTurnError::Unexpected {
+				token: ")".into(),
+				column: 4
+			}
+		);
+		assert!(Turn::from_str("R U").is_err());
+
+		// Repetitions can't expand to huge sequences (or overflow)
+		assert_eq!(
+			err("(R U)9999999999"),
+			ParseTurnError::TooLong {
+				token: "9999999999".into(),
+				column: 6
+			}
+		);
+		assert!(matches!(
+			err("[R, (R U)99999999999999999999]"),
+			ParseTurnError::TooLong { .. }
+		));
+		assert_eq!(parse_turns("()99999999999999999999").unwrap(), vec![]);
+		assert_eq!(parse_turns("R99999999999999999999").unwrap().len(), 1);
+	}
+}
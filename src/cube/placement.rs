@@ -0,0 +1,322 @@
+//! A dynamic programming over the placements of one kind of pieces (corners or edges).
+//!
+//! The positions are filled in order, and a state is the set of used pieces,
+//! the sum of the orientations and the parity of the permutation.
+//! A placement is valid, if the orientations sum up to 0 and the permutation has the wanted parity.
+
+/// The weight of the choices in a placement, like a count or a cost
+pub trait Weight: Copy + PartialEq {
+	/// The weight of no placements at all
+	const ZERO: Self;
+	/// The weight of the empty placement
+	const ONE: Self;
+
+	/// Return the weight of two choices made one after another
+	fn extend(self, other: Self) -> Self;
+	/// Return the weight of two alternative choices
+	fn combine(self, other: Self) -> Self;
+}
+
+/// Counts the placements
+impl Weight for u128 {
+	const ZERO: Self = 0;
+	const ONE: Self = 1;
+
+	fn extend(self, other: Self) -> Self {
+		self * other
+	}
+
+	fn combine(self, other: Self) -> Self {
+		self + other
+	}
+}
+
+/// The cost of the cheapest placement, summing up the costs of the choices
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Cost(pub usize);
+
+impl Weight for Cost {
+	const ZERO: Self = Cost(usize::MAX);
+	const ONE: Self = Cost(0);
+
+	fn extend(self, other: Self) -> Self {
+		Cost(self.0.saturating_add(other.0))
+	}
+
+	fn combine(self, other: Self) -> Self {
+		self.min(other)
+	}
+}
+
+/// The placements of one kind of pieces, whose orientations sum up to 0
+pub struct Placements<W> {
+	num_ori: usize,
+	/// The pieces, orientations and weights of the choices at each position
+	pub candidates: Vec<Vec<(usize, usize, W)>>,
+	/// The weight of the ways to fill the first positions with the pieces of the mask,
+	/// for every sum of the orientations and parity of the permutation
+	forward: Vec<W>,
+	/// The weight of the ways to fill the remaining positions, starting at the state,
+	/// so that the orientations sum up to 0 and the permutation is even
+	backward: Vec<W>,
+}
+
+impl<W: Weight> Placements<W> {
+	pub fn new(candidates: Vec<Vec<(usize, usize, W)>>, num_ori: usize) -> Self {
+		let n = candidates.len();
+		let states = (1 << n) * num_ori * 2;
+		let mut placements = Self {
+			num_ori,
+			candidates,
+			forward: vec![],
+			backward: vec![],
+		};
+
+		let mut forward = vec![W::ZERO; states];
+		forward[placements.index(0, 0, 0)] = W::ONE;
+		for mask in 0..(1usize << n) {
+			let pos = mask.count_ones() as usize;
+			if pos == n {
+				continue;
+			}
+
+			for ori_sum in 0..num_ori {
+				for parity in 0..2 {
+					let weight = forward[placements.index(mask, ori_sum, parity)];
+					if weight == W::ZERO {
+						continue;
+					}
+					for (next, i) in placements.successors(pos, mask, ori_sum, parity) {
+						let choice = placements.candidates[pos][i].2;
+						forward[next] = forward[next].combine(weight.extend(choice));
+					}
+				}
+			}
+		}
+
+		let mut backward = vec![W::ZERO; states];
+		let full = (1 << n) - 1;
+		backward[placements.index(full, 0, 0)] = W::ONE;
+		for mask in (0..full).rev() {
+			let pos = mask.count_ones() as usize;
+			for ori_sum in 0..num_ori {
+				for parity in 0..2 {
+					let weight = placements
+						.successors(pos, mask, ori_sum, parity)
+						.map(|(next, i)| placements.candidates[pos][i].2.extend(backward[next]))
+						.fold(W::ZERO, W::combine);
+					backward[placements.index(mask, ori_sum, parity)] = weight;
+				}
+			}
+		}
+
+		placements.forward = forward;
+		placements.backward = backward;
+		placements
+	}
+
+	fn index(&self, mask: usize, ori_sum: usize, parity: usize) -> usize {
+		(mask * self.num_ori + ori_sum) * 2 + parity
+	}
+
+	/// Return the mask, orientation sum and parity of a state index
+	fn state(&self, index: usize) -> (usize, usize, usize) {
+		(
+			index / 2 / self.num_ori,
+			(index / 2) % self.num_ori,
+			index % 2,
+		)
+	}
+
+	/// Return the states after placing a piece at the next position and the used candidate
+	fn successors(
+		&self,
+		pos: usize,
+		mask: usize,
+		ori_sum: usize,
+		parity: usize,
+	) -> impl Iterator<Item = (usize, usize)> + '_ {
+		self.candidates[pos]
+			.iter()
+			.enumerate()
+			.filter(move |(_, (piece, _, _))| mask & (1 << piece) == 0)
+			.map(move |(i, (piece, ori, _))| {
+				// The pieces placed before, which are greater
+				let inversions = (mask >> piece).count_ones() as usize;
+				let next = self.index(
+					mask | (1 << piece),
+					(ori_sum + ori) % self.num_ori,
+					(parity + inversions) % 2,
+				);
+				(next, i)
+			})
+	}
+
+	/// Return the weight of all placements with the parity
+	pub fn total(&self, parity: usize) -> W {
+		// The backward weight ends with an even permutation, so start with the parity
+		self.backward[self.index(0, 0, parity)]
+	}
+
+	/// Return the weight of the placements with the parity, which use each candidate
+	pub fn usage(&self, parity: usize) -> Vec<Vec<W>> {
+		let n = self.candidates.len();
+		let mut usage: Vec<Vec<W>> = self
+			.candidates
+			.iter()
+			.map(|candidates| vec![W::ZERO; candidates.len()])
+			.collect();
+
+		for mask in 0..(1usize << n) {
+			let pos = mask.count_ones() as usize;
+			if pos == n {
+				continue;
+			}
+
+			for ori_sum in 0..self.num_ori {
+				for p in 0..2 {
+					let weight = self.forward[self.index(mask, ori_sum, p)];
+					if weight == W::ZERO {
+						continue;
+					}
+					for (next, i) in self.successors(pos, mask, ori_sum, p) {
+						// The backward weight ends with an even permutation, flip it for odd ones
+						let through = weight
+							.extend(self.candidates[pos][i].2)
+							.extend(self.backward[next ^ parity]);
+						usage[pos][i] = usage[pos][i].combine(through);
+					}
+				}
+			}
+		}
+
+		usage
+	}
+
+	/// Return the pieces and orientations of a placement with the parity,
+	/// whose weight is the total weight (the cheapest one for costs)
+	pub fn best(&self, parity: usize) -> Option<Vec<(usize, usize)>> {
+		let mut state = self.index(0, 0, parity);
+		if self.backward[state] == W::ZERO {
+			return None;
+		}
+
+		let mut pieces = vec![];
+		for pos in 0..self.candidates.len() {
+			let (mask, ori_sum, parity) = self.state(state);
+			let (next, i) = self
+				.successors(pos, mask, ori_sum, parity)
+				.find(|(next, i)| {
+					self.candidates[pos][*i].2.extend(self.backward[*next]) == self.backward[state]
+				})?;
+			let (piece, ori, _) = self.candidates[pos][i];
+			pieces.push((piece, ori));
+			state = next;
+		}
+		Some(pieces)
+	}
+
+	/// Return an iterator over all placements with the parity
+	pub fn iter(&self, parity: usize) -> PlacementIter<'_, W> {
+		let stack = if self.total(parity) == W::ZERO {
+			vec![]
+		} else {
+			vec![(0, 0, parity, 0)]
+		};
+		PlacementIter {
+			placements: self,
+			stack,
+			pieces: vec![],
+		}
+	}
+}
+
+/// A depth-first search over the placements, which only enters states that can be completed
+pub struct PlacementIter<'a, W> {
+	placements: &'a Placements<W>,
+	/// The mask, orientation sum and parity of each state (the parity starts with
+	/// the parity searched for, so the search always ends with an even one)
+	/// and the next candidate to try
+	stack: Vec<(usize, usize, usize, usize)>,
+	/// The chosen pieces and orientations
+	pieces: Vec<(usize, usize)>,
+}
+
+impl<W: Weight> Iterator for PlacementIter<'_, W> {
+	type Item = Vec<(usize, usize)>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let placements = self.placements;
+		let n = placements.candidates.len();
+
+		while let Some((mask, ori_sum, parity, start)) = self.stack.last().copied() {
+			let pos = self.stack.len() - 1;
+
+			let next = placements
+				.successors(pos, mask, ori_sum, parity)
+				.find(|(next, i)| *i >= start && placements.backward[*next] != W::ZERO);
+
+			let Some((next, i)) = next else {
+				self.stack.pop();
+				self.pieces.pop();
+				continue;
+			};
+
+			self.stack.last_mut().unwrap().3 = i + 1;
+			let (piece, ori, _) = placements.candidates[pos][i];
+			self.pieces.push((piece, ori));
+
+			if pos + 1 == n {
+				let pieces = self.pieces.clone();
+				self.pieces.pop();
+				return Some(pieces);
+			}
+
+			let (mask, ori_sum, parity) = placements.state(next);
+			self.stack.push((mask, ori_sum, parity, 0));
+		}
+
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Every corner with every orientation at every position
+	fn corners<W: Weight>(weight: impl Fn(usize, usize, usize) -> W) -> Placements<W> {
+		let candidates = (0..8)
+			.map(|pos| {
+				(0..8)
+					.flat_map(|piece| (0..3).map(move |ori| (piece, ori)))
+					.map(|(piece, ori)| (piece, ori, weight(pos, piece, ori)))
+					.collect()
+			})
+			.collect();
+		Placements::new(candidates, 3)
+	}
+
+	#[test]
+	fn count() {
+		let placements = corners(|_, _, _| 1u128);
+		// 8! / 2 permutations of each parity and 3^7 orientations
+		for parity in 0..2 {
+			assert_eq!(placements.total(parity), 20_160 * 2_187);
+			assert!(placements.usage(parity)[0]
+				.iter()
+				.all(|u| *u == 20_160 * 2_187 / 24));
+		}
+		assert_eq!(placements.iter(0).take(5).count(), 5);
+	}
+
+	#[test]
+	fn cost() {
+		// Every piece and orientation costs 1, except the solved one
+		let placements = corners(|pos, piece, ori| Cost((pos != piece || ori != 0) as usize));
+		assert_eq!(placements.total(0), Cost(0));
+		assert_eq!(placements.best(0), Some((0..8).map(|i| (i, 0)).collect()));
+		// An odd permutation swaps two pieces
+		assert_eq!(placements.total(1), Cost(2));
+	}
+}
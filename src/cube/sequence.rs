@@ -0,0 +1,263 @@
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use strum::IntoEnumIterator;
+
+use super::{
+	cubiecube::{get_symmetry, CubieCube, NUM_SYMMETRIES},
+	notation::{parse_turns, ParseTurnError},
+	turn::*,
+	RubiksCube,
+};
+
+/// A sequence of turns, like an algorithm or a solution
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Sequence {
+	pub turns: Vec<Turn>,
+}
+
+/// Return the index of a turn in the list of all turns
+const fn turn_index(turn: Turn) -> usize {
+	turn.side as usize * NUM_TURNWISES + turn.wise as usize
+}
+
+/// Return the number of clockwise quarter turns of a turn
+const fn quarters(wise: TurnWise) -> usize {
+	wise as usize + 1
+}
+
+/// Return the turn of the side, which turns it the given number of clockwise quarters.
+/// Return None if the quarters cancel each other.
+fn from_quarters(side: TurnType, quarters: usize) -> Option<Turn> {
+	let wise = match quarters % 4 {
+		1 => TurnWise::Clockwise,
+		2 => TurnWise::Double,
+		3 => TurnWise::CounterClockwise,
+		_ => return None,
+	};
+	Some(Turn { side, wise })
+}
+
+lazy_static! {
+	/// SYMMETRY_TURNS[s][i] is the turn with index i, seen through the symmetry s
+	static ref SYMMETRY_TURNS: Vec<Vec<Turn>> = {
+		let turns: Vec<Turn> = TurnType::iter()
+			.flat_map(|side| TurnWise::iter().map(move |wise| Turn { side, wise }))
+			.collect();
+		let cubes: Vec<CubieCube> = turns
+			.iter()
+			.map(|turn| {
+				let mut cube = CubieCube::new();
+				cube.apply_turn(*turn);
+				cube
+			})
+			.collect();
+
+		(0..NUM_SYMMETRIES)
+			.map(|s| {
+				cubes
+					.iter()
+					.map(|cube| {
+						let sym = get_symmetry(cube, s);
+						let i = cubes.iter().position(|c| *c == sym).unwrap();
+						turns[i]
+					})
+					.collect()
+			})
+			.collect()
+	};
+}
+
+impl Sequence {
+	pub fn new(turns: Vec<Turn>) -> Self {
+		Self { turns }
+	}
+
+	pub fn len(&self) -> usize {
+		self.turns.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.turns.is_empty()
+	}
+
+	/// Return the sequence which undoes this sequence
+	pub fn inverse(&self) -> Self {
+		let turns = self
+			.turns
+			.iter()
+			.rev()
+			.map(|turn| {
+				let mut turn = *turn;
+				turn.invert();
+				turn
+			})
+			.collect();
+		Self { turns }
+	}
+
+	/// Return the mirrored sequence, where the mirror lies between the two sides of the axis.
+	/// For example, the L/R mirror of "R U R'" is "L' U' L".
+	pub fn mirror(&self, axis: Axis) -> Self {
+		use TurnType::*;
+
+		let turns = self
+			.turns
+			.iter()
+			.map(|turn| {
+				let mut turn = *turn;
+				if turn.side.axis() != axis {
+					turn.invert();
+					return turn;
+				}
+
+				// The slices and rotations of the axis are not affected,
+				// but the sides swap and are turned the other way around.
+				let side = match turn.side {
+					U => D,
+					D => U,
+					B => F,
+					F => B,
+					L => R,
+					R => L,
+					Uw => Dw,
+					Dw => Uw,
+					Bw => Fw,
+					Fw => Bw,
+					Lw => Rw,
+					Rw => Lw,
+					_ => return turn,
+				};
+				turn.side = side;
+				turn.invert();
+				turn
+			})
+			.collect();
+		Self { turns }
+	}
+
+	/// Return the sequence seen through the symmetry with the given index
+	/// (see `cubiecube::get_symmetry`).
+	/// Applying the result to a cube has the same effect as applying the symmetry to the cube,
+	/// this sequence and the inverse symmetry.
+	pub fn rotate(&self, symmetry: usize) -> Self {
+		let table = &SYMMETRY_TURNS[symmetry];
+		let turns = self.turns.iter().map(|t| table[turn_index(*t)]).collect();
+		Self { turns }
+	}
+
+	/// Return the sequence with redundant turns removed.
+	/// Turns around the same axis commute, so turns of the same side or slice are merged
+	/// even if other turns around that axis lie between them (U D U' becomes D).
+	pub fn simplify(&self) -> Self {
+		let mut out: Vec<Turn> = vec![];
+
+		for turn in self.turns.iter() {
+			// The turns at the end, which commute with the turn
+			let axis = turn.side.axis();
+			let start = out
+				.iter()
+				.rposition(|t| t.side.axis() != axis)
+				.map_or(0, |i| i + 1);
+
+			match out[start..].iter().position(|t| t.side == turn.side) {
+				Some(i) => {
+					let i = start + i;
+					match from_quarters(turn.side, quarters(out[i].wise) + quarters(turn.wise)) {
+						Some(merged) => out[i] = merged,
+						None => {
+							out.remove(i);
+						}
+					}
+				}
+				None => out.push(*turn),
+			}
+		}
+
+		Self { turns: out }
+	}
+}
+
+impl From<Vec<Turn>> for Sequence {
+	fn from(turns: Vec<Turn>) -> Self {
+		Self { turns }
+	}
+}
+
+impl From<Sequence> for Vec<Turn> {
+	fn from(sequence: Sequence) -> Self {
+		sequence.turns
+	}
+}
+
+impl FromStr for Sequence {
+	type Err = ParseTurnError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		parse_turns(s).map(Self::from)
+	}
+}
+
+impl std::fmt::Display for Sequence {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		for (i, turn) in self.turns.iter().enumerate() {
+			if i > 0 {
+				write!(f, " ")?;
+			}
+			turn.fmt(f)?;
+		}
+		Ok(())
+	}
+}
+
+/// Sequences are serialized in their notation (e.g. "R U R' U'")
+#[cfg(feature = "serde")]
+impl serde::Serialize for Sequence {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Sequence {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		s.parse().map_err(serde::de::Error::custom)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cube::arraycube::{self, ArrayCube};
+
+	fn seq(s: &str) -> Sequence {
+		Sequence::from_str(s).unwrap()
+	}
+
+	#[test]
+	/// Check the algebra on some well known examples
+	fn sequence_algebra() {
+		assert_eq!(seq("R U R' F2").inverse(), seq("F2 R U' R'"));
+		assert_eq!(seq("R U R' U'").mirror(Axis::LR), seq("L' U' L U"));
+		assert_eq!(seq("M' U M Rw x").mirror(Axis::LR), seq("M' U' M Lw' x"));
+		assert_eq!(seq("U D U'").simplify(), seq("D"));
+		assert_eq!(seq("R U D U' D' R' F2 F2").simplify(), seq(""));
+		assert_eq!(seq("R L R M' R2").simplify(), seq("L M'"));
+	}
+
+	#[test]
+	/// Rotating a sequence is the same as rotating the cube
+	fn sequence_symmetries() {
+		let turns = seq("R U2 F' M Rw E' x S2 y' D Bw");
+		let cube = ArrayCube::from(turns.turns.clone());
+
+		for s in 0..NUM_SYMMETRIES {
+			let rotated = ArrayCube::from(turns.rotate(s).turns);
+			assert_eq!(rotated, arraycube::get_symmetry(&cube, s));
+		}
+
+		// The symmetry with index 1 is the L/R mirror
+		assert_eq!(turns.rotate(1), turns.mirror(Axis::LR));
+	}
+}
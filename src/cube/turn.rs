@@ -2,7 +2,8 @@ use rand::Rng;
 use strum::{EnumCount, IntoEnumIterator};
 
 use std::iter::Iterator;
-use std::str::FromStr;
+
+use super::{cubiecube::CubieCube, RubiksCube, Side};
 
 /// Total number of ways to adjust your turn
 pub const NUM_TURNWISES: usize = 3;
@@ -19,6 +20,7 @@ pub const NUM_TURNWISES: usize = 3;
 	strum::EnumCount,
 	strum::EnumString,
 	strum::Display,
+	strum::FromRepr,
 )]
 #[repr(u8)]
 pub enum TurnType {
@@ -28,6 +30,106 @@ pub enum TurnType {
 	F, // Front
 	L, // Left
 	R, // Right
+	M, // Middle (between L and R, turned like L)
+	E, // Equator (between U and D, turned like D)
+	S, // Standing (between F and B, turned like F)
+	// Wide turns (a side together with the adjacent slice)
+	#[strum(to_string = "Uw", serialize = "u")]
+	Uw,
+	#[strum(to_string = "Dw", serialize = "d")]
+	Dw,
+	#[strum(to_string = "Bw", serialize = "b")]
+	Bw,
+	#[strum(to_string = "Fw", serialize = "f")]
+	Fw,
+	#[strum(to_string = "Lw", serialize = "l")]
+	Lw,
+	#[strum(to_string = "Rw", serialize = "r")]
+	Rw,
+	// Rotations of the whole cube, turned like R, U and F respectively
+	#[strum(serialize = "x")]
+	X,
+	#[strum(serialize = "y")]
+	Y,
+	#[strum(serialize = "z")]
+	Z,
+}
+
+/// The axes a cube can be turned around, named by the sides they pass through
+#[derive(
+	Clone, Copy, PartialEq, Eq, Hash, Debug, strum::EnumIter, strum::EnumString, strum::Display,
+)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Axis {
+	UD,
+	FB,
+	LR,
+}
+
+/// The number of turntypes which aren't made of other turntypes
+pub const NUM_BASIC_TURNTYPES: usize = 9;
+
+impl TurnType {
+	/// Return the axis the turn turns around
+	pub const fn axis(&self) -> Axis {
+		use TurnType::*;
+		match self {
+			U | D | E | Uw | Dw | Y => Axis::UD,
+			B | F | S | Bw | Fw | Z => Axis::FB,
+			L | R | M | Lw | Rw | X => Axis::LR,
+		}
+	}
+
+	/// Return true if the turn moves one of the outer sides
+	pub const fn is_side(&self) -> bool {
+		(*self as usize) < 6
+	}
+
+	/// Return true if the turn moves a middle slice rather than an outer side
+	pub const fn is_slice(&self) -> bool {
+		matches!(self, TurnType::M | TurnType::E | TurnType::S)
+	}
+
+	/// Return true if the turn rotates the whole cube
+	pub const fn is_rotation(&self) -> bool {
+		matches!(self, TurnType::X | TurnType::Y | TurnType::Z)
+	}
+
+	/// Return the clockwise quarter turns of sides and slices this turn is made of
+	/// Sides and slices aren't made of other turns, so they return an empty list.
+	pub const fn parts(&self) -> &'static [Turn] {
+		use TurnType::*;
+		use TurnWise::*;
+		macro_rules! q {
+			($side:ident, $wise:ident) => {
+				Turn {
+					side: $side,
+					wise: $wise,
+				}
+			};
+		}
+
+		match self {
+			Uw => &[q!(U, Clockwise), q!(E, CounterClockwise)],
+			Dw => &[q!(D, Clockwise), q!(E, Clockwise)],
+			Bw => &[q!(B, Clockwise), q!(S, CounterClockwise)],
+			Fw => &[q!(F, Clockwise), q!(S, Clockwise)],
+			Lw => &[q!(L, Clockwise), q!(M, Clockwise)],
+			Rw => &[q!(R, Clockwise), q!(M, CounterClockwise)],
+			X => &[
+				q!(R, Clockwise),
+				q!(M, CounterClockwise),
+				q!(L, CounterClockwise),
+			],
+			Y => &[
+				q!(U, Clockwise),
+				q!(E, CounterClockwise),
+				q!(D, CounterClockwise),
+			],
+			Z => &[q!(F, Clockwise), q!(S, Clockwise), q!(B, CounterClockwise)],
+			_ => &[],
+		}
+	}
 }
 
 /// Total number of turntypes
@@ -75,6 +177,54 @@ impl Turn {
 	}
 }
 
+impl Turn {
+	/// Split the turn into turns of the outer sides, followed by a rotation of the whole cube.
+	/// Both are relative to the current orientation of the cube.
+	fn split(&self) -> (Vec<Turn>, Option<Turn>) {
+		use TurnType::*;
+
+		let same = |side| Turn {
+			side,
+			wise: self.wise,
+		};
+		let inverse = |side| {
+			let mut turn = same(side);
+			turn.invert();
+			turn
+		};
+
+		match self.side {
+			U | D | B | F | L | R => (vec![*self], None),
+			M => (vec![same(R), inverse(L)], Some(inverse(X))),
+			E => (vec![same(U), inverse(D)], Some(inverse(Y))),
+			S => (vec![inverse(F), same(B)], Some(same(Z))),
+			Uw => (vec![same(D)], Some(same(Y))),
+			Dw => (vec![same(U)], Some(inverse(Y))),
+			Bw => (vec![same(F)], Some(inverse(Z))),
+			Fw => (vec![same(B)], Some(same(Z))),
+			Lw => (vec![same(R)], Some(inverse(X))),
+			Rw => (vec![same(L)], Some(same(X))),
+			X | Y | Z => (vec![], Some(*self)),
+		}
+	}
+}
+
+/// Turns are serialized in their notation (e.g. "R'")
+#[cfg(feature = "serde")]
+impl serde::Serialize for Turn {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Turn {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		s.parse().map_err(serde::de::Error::custom)
+	}
+}
+
 impl std::fmt::Display for Turn {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		self.side.fmt(f)?;
@@ -82,48 +232,38 @@ impl std::fmt::Display for Turn {
 	}
 }
 
-impl FromStr for Turn {
-	type Err = ();
+/// Rewrite a sequence with turns of the outer sides only, relative to fixed centers.
+///
+/// Slices, wide turns and rotations move the centers. The turns following them are relabeled
+/// to the sides they actually turn, so the result leads to the same cube as the given sequence,
+/// except that the whole cube is turned back until the centers are in their home position.
+pub fn normalize(turns: &[Turn]) -> Vec<Turn> {
+	// The centers of the frame tell which side is currently at which position
+	let mut frame = CubieCube::new();
+	let mut out = vec![];
 
-	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		let substr = {
-			if s.ends_with('\'') || s.ends_with('2') {
-				&s[0..s.len() - 1]
-			} else {
-				&s[0..s.len()]
-			}
-		};
+	for turn in turns {
+		let (sides, rotation) = turn.split();
 
-		let side = match TurnType::from_str(substr) {
-			Ok(res) => res,
-			Err(_) => return Err(()),
-		};
-
-		let wise = {
-			if s.ends_with('\'') {
-				TurnWise::CounterClockwise
-			} else if s.ends_with('2') {
-				TurnWise::Double
-			} else {
-				TurnWise::Clockwise
-			}
-		};
+		for t in sides {
+			let position = Side::from_repr(t.side as u8).unwrap();
+			let side = TurnType::from_repr(frame.center_at(position) as u8).unwrap();
+			out.push(Turn { side, wise: t.wise });
+		}
 
-		Ok(Self { side, wise })
+		if let Some(rotation) = rotation {
+			frame.apply_turn(rotation);
+		}
 	}
-}
 
-pub fn parse_turns<T>(item: T) -> Result<Vec<Turn>, ()>
-where
-	T: Into<String>,
-{
-	item.into().split_whitespace().map(Turn::from_str).collect()
+	out
 }
 
 /// Return a random sequence of turns of with length n
+/// The sequence only contains turns of the outer sides.
 #[allow(unused)]
 pub fn random_sequence(n: usize) -> Vec<Turn> {
-	let sides: Vec<_> = TurnType::iter().collect();
+	let sides: Vec<_> = TurnType::iter().filter(|t| t.is_side()).collect();
 	let wises: Vec<_> = TurnWise::iter().collect();
 	let mut rng = rand::thread_rng();
 
@@ -136,3 +276,41 @@ pub fn random_sequence(n: usize) -> Vec<Turn> {
 		})
 		.collect()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cube::{arraycube::ArrayCube, notation::parse_turns};
+
+	#[test]
+	/// Wide turns are printed in one notation, but parsed in both
+	fn wide_turn_notation() {
+		let turns = parse_turns("Rw r' Uw2 u x y' z2").unwrap();
+		let printed: Vec<_> = turns.iter().map(|t| t.to_string()).collect();
+		assert_eq!(printed, ["Rw", "Rw'", "Uw2", "Uw", "x", "y'", "z2"]);
+	}
+
+	#[test]
+	/// The normalized sequence leads to the same cube, up to a rotation of the whole cube
+	fn normalize_sequence() {
+		let turns = parse_turns("y R Uw2 M' r U' x' E S2 Fw' z Lw B Dw' b").unwrap();
+		let normalized = normalize(&turns);
+		assert!(normalized.iter().all(|t| t.side.is_side()));
+
+		let cube = ArrayCube::from(normalized);
+		let rotations: Vec<_> = TurnType::iter()
+			.filter(|t| t.is_rotation())
+			.flat_map(|side| TurnWise::iter().map(move |wise| Turn { side, wise }))
+			.collect();
+
+		// Some rotation must turn the centers back
+		let found = rotations.iter().any(|r1| {
+			rotations.iter().any(|r2| {
+				let mut rotated = ArrayCube::from(turns.clone());
+				rotated.apply_turns(vec![*r1, *r2]);
+				rotated == cube
+			})
+		});
+		assert!(found);
+	}
+}
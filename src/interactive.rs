@@ -12,6 +12,9 @@ const OFFSET_Y: i32 = 1;
 
 const CUBEDATA_LEN: usize = CUBE_AREA * 6;
 
+/// The value of a sticker with an unknown color
+const UNKNOWN_COLOR: u8 = NUM_SIDES as u8;
+
 /// Draw one side of a cube
 fn draw_facelet(win: &Window, x: usize, y: usize, data: &[u8]) {
 	let col = if DISPLAY_GRID[y][x] < CUBEDATA_LEN {
@@ -23,6 +26,13 @@ fn draw_facelet(win: &Window, x: usize, y: usize, data: &[u8]) {
 	let cx = x as i32 * 6 + OFFSET_X;
 	let cy = y as i32 * 3 + OFFSET_Y;
 
+	if col == UNKNOWN_COLOR as u32 {
+		win.attron(COLOR_PAIR(1));
+		win.mvprintw(cy, cx, "???");
+		win.mvprintw(cy + 1, cx, "   ");
+		return;
+	}
+
 	win.attron(COLOR_PAIR(col + 1));
 	win.mvprintw(cy, cx, "███");
 	win.mvprintw(cy + 1, cx, "▀▀▀");
@@ -49,9 +59,18 @@ fn draw_cursor(win: &Window, x: usize, y: usize, clear: bool) {
 	win.mvprintw(cy, cx + 3, c);
 }
 
+/// Return the cube string of the stickers, with '?' for unknown ones
+fn get_string(data: &[u8]) -> String {
+	data.iter()
+		.map(|c| match *c {
+			UNKNOWN_COLOR => completion::UNKNOWN,
+			c => (b'a' + c) as char,
+		})
+		.collect()
+}
+
 fn get_cube(data: &[u8]) -> Result<ArrayCube, arraycube::FromStrError> {
-	let s: String = data.iter().map(|c| (b'a' + c) as char).collect();
-	ArrayCube::from_str(&s)
+	ArrayCube::from_str(&get_string(data))
 }
 
 /// Draw the entire screen
@@ -90,7 +109,8 @@ fn init(win: &Window, cube: &[u8]) {
 	win.attron(COLOR_PAIR(1));
 
 	win.printw("Move cursor with (i,j,k,l)\n");
-	win.printw("Set the color with (w,y|g,b|o,r)\n");
+	win.printw("Set the color with (w,y|g,b|o,r) or mark it as unknown with ?\n");
+	win.printw("Fill in the stickers forced by the others with (shift+)F\n");
 	win.printw("Clear the cube with (shift+)C\n\n");
 
 	win.printw("Press (shift+)Q to quit, if the cube is solvable.");
@@ -108,6 +128,30 @@ fn update_solvability_message(win: &Window, data: &[u8]) {
 	win.mv(3 * CUBE_DIM as i32 * 3 + 3, 0);
 	win.clrtoeol();
 
+	// With unknown stickers, show how many cubes fit to the known ones
+	if data.contains(&UNKNOWN_COLOR) {
+		let completion = completion::Completion::from_native(&get_string(data)).unwrap();
+		match completion.count() {
+			0 => {
+				win.attron(COLOR_PAIR(5));
+				win.printw("No solvable cube fits to the stickers");
+			}
+			1 => {
+				win.attron(COLOR_PAIR(3));
+				win.printw("A single cube fits to the stickers");
+			}
+			count => {
+				win.attron(COLOR_PAIR(3));
+				win.printw(format!(
+					"{} cubes fit to the stickers (quitting needs exactly one)",
+					count
+				));
+			}
+		}
+		update_diagnosis(win, data);
+		return;
+	}
+
 	// Display message depending on the solvabilty of the cube
 	match get_solvability(data) {
 		Ok(()) => {
@@ -119,6 +163,55 @@ fn update_solvability_message(win: &Window, data: &[u8]) {
 			win.printw(e.to_string());
 		}
 	}
+
+	update_diagnosis(win, data);
+}
+
+/// Show all problems of the cube and the stickers to change below the instructions
+fn update_diagnosis(win: &Window, data: &[u8]) {
+	win.mv(3 * CUBE_DIM as i32 * 3 + 11, 0);
+	win.clrtobot();
+
+	// Problems can only be found with every sticker known
+	if data.contains(&UNKNOWN_COLOR) {
+		return;
+	}
+
+	let colors: Vec<Side> = data.iter().map(|c| Side::from_repr(*c).unwrap()).collect();
+	let diagnosis = diagnosis::Diagnosis::new(&colors);
+	if diagnosis.is_solvable() {
+		return;
+	}
+
+	win.attron(COLOR_PAIR(5));
+	for problem in diagnosis.problems.iter() {
+		win.printw(format!("{}\n", problem));
+	}
+
+	win.attron(COLOR_PAIR(1));
+	if !diagnosis.fixes.is_empty() {
+		win.printw("\nChange these stickers to make the cube solvable:\n");
+		for fix in diagnosis.fixes.iter() {
+			win.printw(format!("  {}\n", fix));
+		}
+	}
+	if let Some(rotation) = &diagnosis.rotation {
+		let rotation: Vec<String> = rotation.iter().map(|turn| turn.to_string()).collect();
+		win.printw(format!(
+			"\nRotate the cube with {} to put the centers on their sides\n",
+			rotation.join(" ")
+		));
+	}
+}
+
+/// Fill in every unknown sticker, which has the same color in all fitting cubes
+fn fill_forced(data: &mut [u8]) {
+	let completion = completion::Completion::from_native(&get_string(data)).unwrap();
+	for (c, sticker) in data.iter_mut().zip(completion.stickers) {
+		if let Some(side) = sticker {
+			*c = side as u8;
+		}
+	}
 }
 
 /// Handle the interactive mode
@@ -137,6 +230,7 @@ pub fn interactive_mode() -> String {
 			let mut ny = y;
 
 			if let Input::Character(c) = key {
+				#[allow(clippy::collapsible_match)]
 				match c {
 					// Cursor up
 					'i' => {
@@ -165,16 +259,17 @@ pub fn interactive_mode() -> String {
 							nx += 1;
 						}
 					}
-					'w' | 'y' | 'g' | 'b' | 'r' | 'o' => {
+					'w' | 'y' | 'g' | 'b' | 'r' | 'o' | '?' => {
 						let side = match c {
-							'w' => Side::Up,
-							'y' => Side::Down,
-							'g' => Side::Back,
-							'b' => Side::Front,
-							'r' => Side::Left,
-							'o' => Side::Right,
+							'w' => Side::Up as u8,
+							'y' => Side::Down as u8,
+							'g' => Side::Back as u8,
+							'b' => Side::Front as u8,
+							'r' => Side::Left as u8,
+							'o' => Side::Right as u8,
+							'?' => UNKNOWN_COLOR,
 							_ => panic!("Undefined behaviour"),
-						} as u8;
+						};
 
 						let idx = DISPLAY_GRID[y][x];
 						// Check that it isn't the cener piece and else apply it
@@ -189,7 +284,23 @@ pub fn interactive_mode() -> String {
 							.map(|i| i as u8 / CUBE_AREA as u8)
 							.collect();
 					}
-					'Q' => break,
+					'F' => {
+						fill_forced(&mut data);
+						draw_cube(&win, &data);
+						update_solvability_message(&win, &data);
+					}
+					'Q' => {
+						// Only quit with unknown stickers, if they are all forced
+						if data.contains(&UNKNOWN_COLOR) {
+							fill_forced(&mut data);
+							draw_cube(&win, &data);
+							update_solvability_message(&win, &data);
+							if data.contains(&UNKNOWN_COLOR) {
+								continue;
+							}
+						}
+						break;
+					}
 					_ => {}
 				}
 			}
@@ -206,9 +317,5 @@ pub fn interactive_mode() -> String {
 
 	endwin();
 
-	for ele in data.iter_mut() {
-		*ele += b'a';
-	}
-
-	String::from_utf8(data).unwrap()
+	get_string(&data)
 }
@@ -0,0 +1,29 @@
+//! Rubik's Cube solver library.
+//!
+//! The crate provides two cube representations ([`ArrayCube`] and [`CubieCube`]),
+//! the turn model ([`Turn`], [`parse_turns`]), the move-count [`metrics`], the solving
+//! algorithms in [`solve`], random-state [`scramble`]s and the [`analysis`] of sequences.
+//!
+//! The pancurses based editor in [`interactive`] is only compiled with the
+//! `interactive` feature, so the library itself does not depend on ncurses.
+
+pub mod analysis;
+pub mod cube;
+#[cfg(feature = "interactive")]
+pub mod interactive;
+pub mod math;
+pub mod metrics;
+pub mod scramble;
+pub mod solve;
+
+pub use cube::{
+	arraycube::{ArrayCube, FromStrError},
+	completion::Completion,
+	cubiecube::CubieCube,
+	diagnosis::{Diagnosis, Problem, StickerFix},
+	format::Format,
+	notation::*,
+	sequence::Sequence,
+	turn::*,
+	*,
+};
@@ -1,28 +1,15 @@
 use std::str::FromStr;
 
-use clap::Parser;
-use cubiecube::{CubieCube, CORNER_ORI, CORNER_PERM, EDGE_ORI, EDGE_PERM};
-use math::count_permutation_inversions;
-use rand::prelude::*;
-use strum::{Display, IntoEnumIterator};
-
-mod cube;
-mod interactive;
-pub mod math;
-mod solve;
-
-use cube::{arraycube::ArrayCube, turn::*, *};
-
-#[derive(
-	PartialEq, Eq, Default, Debug, Display, Copy, Clone, strum::EnumString, strum::EnumIter,
-)]
-#[repr(usize)]
-#[non_exhaustive]
-enum SolveAlgorithm {
-	#[default]
-	Kociemba,
-	Thistlewaite,
-}
+use clap::{Parser, Subcommand};
+use rand::{rngs::StdRng, SeedableRng};
+
+use rubiks_solver::{
+	analysis::Analysis,
+	metrics::Metric,
+	scramble::{self, ScrambleType},
+	solve::{self, tables, Progress, SolutionRecord, SolveOptions},
+	ArrayCube, CubieCube, *,
+};
 
 /// Rubik's Cube solver written in Rust
 #[derive(Parser, Debug)]
@@ -30,32 +17,90 @@ enum SolveAlgorithm {
 struct Args {
 	/// Enter the cube interactively
 	/// Entered sequences or shuffles are ignored
+	#[cfg(feature = "interactive")]
 	#[arg(short, long, default_value_t = false)]
 	interactive: bool,
 
 	/// Use a sequence to apply on the solved cube
+	/// (e.g. "R U R' U'", "(R U)3", "[R, U]" or "[F: [R, U]]")
 	#[arg(short, default_value_t = String::new())]
 	sequence: String,
 
+	/// Rewrite the sequence with turns of the outer sides only (removing slices, wide turns
+	/// and rotations), so the centers stay in place and the cube can be solved
+	#[arg(long, default_value_t = false)]
+	normalize: bool,
+
 	/// Set the cube from a string (the same format as when you output the cube via the "-c"-flag)
+	/// Unknown stickers can be entered as '?'
 	#[arg(long, default_value_t = String::new())]
 	set: String,
 
+	/// List this many of the cubes fitting to a --set string with unknown stickers
+	#[arg(long, default_value_t = 0)]
+	completions: usize,
+
+	/// The format of the cube strings of --set and -c: native, kociemba (URFDLB facelets)
+	/// or colors (WYGBRO stickers, any color scheme)
+	#[arg(long, default_value_t = Format::default())]
+	format: Format,
+
 	/// Solve the cube (the output is a sequence)
 	#[arg(long, default_value_t = false)]
 	solve: bool,
 
+	/// Turn the cube into this cube rather than the solved cube when solving
+	/// (a cube string in the format of --format)
+	#[arg(long)]
+	goal: Option<String>,
+
 	/// Output the cube as a string rather than colored
 	#[arg(short, long, default_value_t = false)]
 	char_print: bool,
 
+	/// Print the cube, the solutions or the analysis as JSON
+	#[arg(long, global = true, default_value_t = false)]
+	json: bool,
+
 	/// Scramble the cube
 	#[arg(short, long, default_value_t = false)]
 	random: bool,
 
-	/// Specify the algorithm used for solving
-	#[arg(long, default_value_t = SolveAlgorithm::default())]
-	algorithm: SolveAlgorithm,
+	/// Specify the algorithm used for solving (see --list-algorithm)
+	#[arg(long, default_value_t = solve::default_solver().name().to_string())]
+	algorithm: String,
+
+	/// Keep searching until the solution has at most this many turns
+	#[arg(long)]
+	max_length: Option<usize>,
+
+	/// Stop searching for shorter solutions after this many seconds
+	#[arg(long)]
+	timeout: Option<f64>,
+
+	/// Print the N shortest distinct solutions found, one per line, once the search is done
+	/// (with --progress every solution is also reported when it is found)
+	#[arg(long, default_value_t = 1)]
+	count: usize,
+
+	/// The metric in which solutions are counted and kept short: htm, qtm, stm or etm
+	#[arg(long, default_value_t = Metric::default())]
+	metric: Metric,
+
+	/// Report the progress of long running searches on stderr
+	#[arg(long, default_value_t = false)]
+	progress: bool,
+
+	/// The directory of the pruning tables
+	/// (default: $RUBIKS_SOLVER_TABLE_DIR or the cache directory of the user).
+	/// It's global, so it can also be given after a subcommand
+	#[arg(long, global = true)]
+	table_dir: Option<std::path::PathBuf>,
+
+	/// The encoding of generated pruning tables: byte, nibble (half the size)
+	/// or mod3 (a quarter of the size, but slower at the start of a search)
+	#[arg(long, global = true)]
+	encoding: Option<tables::Encoding>,
 
 	/// Print all possible algorithms and quit
 	#[arg(long, default_value_t = false)]
@@ -65,6 +110,170 @@ struct Args {
 	/// If you want to read the output of the interactive mode, you should use this.
 	#[arg(short, long, default_value_t = String::new())]
 	output: String,
+
+	#[command(subcommand)]
+	command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Precompute, verify and inspect the pruning tables
+	Tables {
+		#[command(subcommand)]
+		action: TablesAction,
+	},
+	/// Print the order and the cycles of the pieces of a sequence
+	Analyze {
+		/// The sequence to analyze
+		sequence: String,
+	},
+	/// Generate random-state scrambles (the inverted solution of a random cube)
+	Scramble {
+		/// The seed of the random generator, to reproduce scrambles
+		#[arg(long)]
+		seed: Option<u64>,
+		/// The number of scrambles, one per line
+		#[arg(long, default_value_t = 1)]
+		count: usize,
+		/// Reject cubes which can be solved in fewer turns (at most 6, checked with the optimal solver)
+		#[arg(long, default_value_t = scramble::MIN_LENGTH)]
+		min_length: usize,
+		/// The cubes to scramble to: random, last-layer, oll, pll, f2l, edges, corners or two-gen
+		#[arg(long = "type", default_value_t = ScrambleType::default())]
+		kind: ScrambleType,
+	},
+}
+
+#[derive(Subcommand, Debug)]
+enum TablesAction {
+	/// Generate all tables which are missing or invalid
+	Build {
+		/// Generate the tables even if they are valid
+		#[arg(long, default_value_t = false)]
+		force: bool,
+	},
+	/// Check that all tables exist and are intact
+	Verify,
+	/// Print how many entries of each table have each distance
+	Stats,
+}
+
+/// Print the progress of a search on stderr
+fn print_progress(progress: &Progress) {
+	eprintln!(
+		"{} depth {} ({} nodes, {:.1}s)",
+		if progress.completed {
+			"Searched"
+		} else {
+			"Searching"
+		},
+		progress.depth,
+		progress.nodes,
+		progress.elapsed.as_secs_f64()
+	);
+}
+
+/// Draw a progress bar on stderr
+fn print_bar(label: &str, done: usize, total: usize) {
+	const WIDTH: usize = 40;
+	let total = total.max(1);
+	let filled = WIDTH * done / total;
+	eprint!(
+		"\r{:<12} [{}{}] {:>3}%",
+		label,
+		"#".repeat(filled),
+		" ".repeat(WIDTH - filled),
+		100 * done / total
+	);
+}
+
+/// Run the tables subcommand
+/// Tables are built with the given encoding, or the default encoding if there is none.
+/// Without an encoding, valid tables are kept in any encoding.
+fn run_tables(
+	action: &TablesAction,
+	encoding: Option<tables::Encoding>,
+	out: &mut dyn std::io::Write,
+) -> std::io::Result<()> {
+	let mut failed = false;
+
+	for table in tables::tables() {
+		let path = table.path();
+		match action {
+			TablesAction::Build { force } => {
+				let up_to_date = table
+					.verify()
+					.is_ok_and(|t| encoding.is_none_or(|e| table.encoding(e) == t.encoding()));
+				if !force && up_to_date {
+					writeln!(
+						out,
+						"{}: up to date ({})",
+						table.description,
+						path.display()
+					)?;
+					continue;
+				}
+
+				let start = std::time::Instant::now();
+				let data = table.generate(
+					&mut |done, total| print_bar(table.description, done, total),
+					encoding.unwrap_or_else(tables::encoding),
+				);
+				eprintln!();
+
+				match table.save(&data) {
+					Ok(()) => writeln!(
+						out,
+						"{}: built in {:.1}s, {} encoding with {} bytes ({})",
+						table.description,
+						start.elapsed().as_secs_f64(),
+						data.encoding(),
+						data.bytes().len(),
+						path.display()
+					)?,
+					Err(e) => {
+						eprintln!("Could not save {}: {}", path.display(), e);
+						failed = true;
+					}
+				}
+			}
+			TablesAction::Verify => match table.verify() {
+				Ok(_) => writeln!(out, "{}: ok ({})", table.description, path.display())?,
+				Err(e) => {
+					writeln!(out, "{}: {} ({})", table.description, e, path.display())?;
+					failed = true;
+				}
+			},
+			TablesAction::Stats => match table.verify() {
+				Ok(data) => {
+					writeln!(
+						out,
+						"{} ({}, {} entries, {} encoding):",
+						table.description,
+						path.display(),
+						data.len(),
+						data.encoding()
+					)?;
+					if data.encoding() == tables::Encoding::Mod3 {
+						writeln!(out, "(only the distances modulo 3 are stored)")?;
+					}
+					for (depth, count) in tables::depth_distribution(&data).iter().enumerate() {
+						let percent = 100.0 * *count as f64 / data.len() as f64;
+						writeln!(out, "{:>4} {:>12} {:>7.3}%", depth, count, percent)?;
+					}
+				}
+				Err(e) => {
+					writeln!(out, "{}: {} ({})", table.description, e, path.display())?;
+					failed = true;
+				}
+			},
+		}
+	}
+
+	if failed {
+		std::process::exit(1);
+	}
+	Ok(())
 }
 
 fn main() -> std::io::Result<()> {
@@ -74,6 +283,13 @@ fn main() -> std::io::Result<()> {
 	}
 
 	let args = Args::parse();
+	if let Some(dir) = &args.table_dir {
+		tables::set_table_dir(dir);
+	}
+	if let Some(encoding) = args.encoding {
+		tables::set_encoding(encoding);
+	}
+
 	// Whether to redirect it to the stdout or a file
 	let mut out: Box<dyn std::io::Write> = if args.output.is_empty() {
 		Box::new(std::io::stdout())
@@ -82,61 +298,112 @@ fn main() -> std::io::Result<()> {
 	};
 	let mut cube = ArrayCube::default();
 
+	match &args.command {
+		Some(Command::Tables { action }) => {
+			return run_tables(action, args.encoding, out.as_mut());
+		}
+		Some(Command::Analyze { sequence }) => {
+			let sequence = Sequence::from_str(sequence).unwrap_or_else(|e| {
+				eprintln!("Given sequence could not be parsed: {}", e);
+				std::process::exit(1);
+			});
+			let analysis = Analysis::new(&sequence);
+			if args.json {
+				writeln!(out, "{}", serde_json::to_string(&analysis)?)?;
+			} else {
+				writeln!(out, "{}", analysis)?;
+			}
+			return Ok(());
+		}
+		Some(Command::Scramble {
+			seed,
+			count,
+			min_length,
+			kind,
+		}) => {
+			let mut rng = match seed {
+				Some(seed) => StdRng::seed_from_u64(*seed),
+				None => StdRng::from_entropy(),
+			};
+			for _ in 0..*count {
+				match scramble::scramble(&mut rng, *kind, *min_length) {
+					Ok(scramble) => writeln!(out, "{}", scramble)?,
+					Err(e) => {
+						eprintln!("Could not generate a scramble: {}", e);
+						std::process::exit(1);
+					}
+				}
+			}
+			return Ok(());
+		}
+		None => {}
+	}
+
 	// List the algorithm and exit
 	if args.list_algorithm {
-		for algo in SolveAlgorithm::iter() {
-			writeln!(out, "{}", algo)?;
+		for solver in solve::solvers() {
+			writeln!(out, "{}: {}", solver.name(), solver.description())?;
 		}
 		return Ok(());
 	}
 
 	// Generate a random input cube
 	if args.random {
-		let mut rng = rand::thread_rng();
-		let mut cubie = CubieCube::new();
-
-		// Generate a cubie by setting random coordinates
-		cubie.set_edge_orientation(rng.gen::<usize>() % EDGE_ORI);
-		cubie.set_corner_orientation(rng.gen::<usize>() % CORNER_ORI);
-
-		let cperm = rng.gen::<usize>() % CORNER_PERM;
-		let mut eperm = rng.gen::<usize>() % EDGE_PERM;
-
-		// The number of swaps have to be even
-		// Which is equivalent to: The number of inversions has to be even.
-		let inv = count_permutation_inversions(cperm);
-		let inv2 = count_permutation_inversions(eperm);
-
-		if (inv + inv2) % 2 == 1 {
-			// It can be proven that the sum over all factoradic digits
-			// are the total number of inversions.
-			// Using the factoradic number system, we can simply change
-			// the second digit by one, which is determined by the first bit.
-			eperm ^= 1;
-		}
-
-		cubie.set_corner_permutation(cperm);
-		cubie.set_edge_permutation(eperm);
-
-		#[cfg(debug_assertions)]
-		assert!(cubie.is_solvable());
-
-		cube = cubie.into();
+		cube = scramble::random_cube(&mut rand::thread_rng()).into();
 	}
 
 	// Parses a cube out of the cube string
-	if !args.set.is_empty() {
-		cube =
-			ArrayCube::from_str(args.set.as_str()).expect("Given cube string couldn't be parsed");
+	if args.set.contains(completion::UNKNOWN) {
+		let completion = args.format.complete(&args.set).unwrap_or_else(|e| {
+			eprintln!("Given cube string couldn't be parsed: {}", e);
+			std::process::exit(1);
+		});
+
+		// A single fitting cube is used, otherwise the deduced stickers are printed
+		match completion.count() {
+			0 => {
+				eprintln!("No solvable cube fits to the given stickers");
+				std::process::exit(1);
+			}
+			1 => cube = completion.completions().next().unwrap(),
+			count => {
+				writeln!(out, "{}", args.format.format_completion(&completion))?;
+				writeln!(out, "{} cubes fit to the given stickers", count)?;
+				for cube in completion.completions().take(args.completions) {
+					writeln!(out, "{}", args.format.format(&cube))?;
+				}
+				return Ok(());
+			}
+		}
+	} else if !args.set.is_empty() {
+		cube = match args.format.parse(&args.set) {
+			Ok(cube) => cube,
+			Err(e) => {
+				eprintln!("Given cube string couldn't be parsed: {}", e);
+				if let Ok(diagnosis) = args.format.diagnose(&args.set) {
+					eprintln!("{}", diagnosis);
+				}
+				std::process::exit(1);
+			}
+		};
 	}
 
 	// Applies turns from args
-	cube.apply_turns(parse_turns(args.sequence).expect("Given input sequence could not be parsed"));
+	let mut turns = parse_turns(args.sequence).unwrap_or_else(|e| {
+		eprintln!("Given input sequence could not be parsed: {}", e);
+		std::process::exit(1);
+	});
+	if args.normalize {
+		turns = normalize(&turns);
+	}
+	let moves_centers = turns.iter().any(|turn| !turn.side.is_side());
+	cube.apply_turns(turns);
 
 	// Use the interactive mode
+	#[cfg(feature = "interactive")]
 	if args.interactive {
 		// Run interactive mode
-		let res = interactive::interactive_mode();
+		let res = rubiks_solver::interactive::interactive_mode();
 		// Parse cube given from the interactive mode
 		cube = match ArrayCube::from_str(&res) {
 			Ok(res) => res,
@@ -150,35 +417,120 @@ fn main() -> std::io::Result<()> {
 			.clone()
 			.try_into()
 			.expect("The given cube couldn't be converted properly");
-
 		if let Err(e) = cubie.check_solvability() {
-			panic!("The given cube is not solvable: {}", e);
+			eprintln!("Given cube isn't solvable: {}", e);
+			let diagnosis = Diagnosis::from(&cube);
+			eprintln!("{}", diagnosis);
+			if diagnosis.rotation.is_some() && moves_centers {
+				eprintln!("Slice turns, wide turns and rotations move the centers, solve such sequences with --normalize");
+			}
+			std::process::exit(1);
 		}
+		let goal: CubieCube = match &args.goal {
+			Some(goal) => {
+				let goal = args.format.parse(goal).unwrap_or_else(|e| {
+					eprintln!("The goal couldn't be parsed: {}", e);
+					std::process::exit(1);
+				});
+				goal.try_into().unwrap_or_else(|e| {
+					eprintln!("The goal isn't solvable: {}", e);
+					std::process::exit(1);
+				})
+			}
+			None => CubieCube::new(),
+		};
 
 		// Choose algorithm to use
-		let seq = match args.algorithm {
-			SolveAlgorithm::Thistlewaite => solve::thistlewhaite::solve(cube),
-			SolveAlgorithm::Kociemba => solve::kociemba::solve(cube),
+		let solver = match solve::get_solver(&args.algorithm) {
+			Ok(solver) => solver,
+			Err(e) => {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			}
 		};
 
-		match seq {
-			Some(turns) => {
-				for turn in turns {
-					write!(out.as_mut(), "{} ", turn)?;
+		let options = SolveOptions {
+			max_length: args.max_length,
+			timeout: args.timeout.map(std::time::Duration::from_secs_f64),
+			progress: if args.progress {
+				Some(std::sync::Arc::new(print_progress))
+			} else {
+				None
+			},
+			count: args.count,
+			metric: args.metric,
+			cancel: None,
+		};
+		let start = std::time::Instant::now();
+		let record = |solution: solve::Solution| {
+			SolutionRecord::new(solver.name(), &solution, start.elapsed())
+		};
+		let mut records: Vec<SolutionRecord> = if args.count > 1 {
+			let solutions = solver
+				.solutions_to(&cubie, &goal, &options)
+				.unwrap_or_else(|e| {
+					eprintln!("Could not solve given Rubik's Cube: {}", e);
+					std::process::exit(1);
+				});
+
+			// The shortest solutions are only known once the search is done
+			let mut records = vec![];
+			for solution in solutions {
+				match solution {
+					Ok(solution) => {
+						let record = record(solution);
+						if args.progress {
+							let len = record.lengths[&args.metric];
+							eprintln!("Found {} ({} {})", record.sequence, len, args.metric);
+						}
+						records.push(record);
+					}
+					Err(e) => {
+						eprintln!("Could not solve given Rubik's Cube: {}", e);
+						if records.is_empty() {
+							std::process::exit(1);
+						}
+					}
 				}
-				writeln!(out.as_mut())?;
-				return Ok(());
 			}
-			None => {
-				panic!("Could not solve given Rubik's Cube!");
+			records
+		} else {
+			match solver.solve_to(&cubie, &goal, &options) {
+				Ok(solution) => vec![record(solution)],
+				Err(e) => {
+					eprintln!("Could not solve given Rubik's Cube: {}", e);
+					std::process::exit(1);
+				}
 			}
+		};
+		records.sort_by_key(|record| record.lengths[&args.metric]);
+		records.truncate(args.count);
+
+		if args.json {
+			// Always an array, so the schema doesn't depend on the number of solutions found
+			writeln!(out, "{}", serde_json::to_string(&records)?)?;
+			return Ok(());
+		}
+
+		for record in records {
+			let len = record.lengths[&args.metric];
+			writeln!(out, "{} ({} {})", record.sequence, len, args.metric)?;
 		}
+		return Ok(());
 	}
 
-	// Print the resulting cube (either as a string or with colors)
-	if args.char_print {
-		let s: String = cube.into();
-		writeln!(out.as_mut(), "{}", s)?;
+	// Print the resulting cube (either as JSON, a string or with colors)
+	if args.json {
+		let cubie = CubieCube::try_from(cube.clone()).ok();
+		let json = serde_json::json!({
+			"cube": args.format.format(&cube),
+			"format": args.format.to_string(),
+			"cubie": cubie,
+			"solved": cube == ArrayCube::default(),
+		});
+		writeln!(out.as_mut(), "{}", json)?;
+	} else if args.char_print {
+		writeln!(out.as_mut(), "{}", args.format.format(&cube))?;
 	} else {
 		cube.print();
 	}
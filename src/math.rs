@@ -32,6 +32,20 @@ pub const fn fast_pow(base: usize, exp: usize) -> usize {
 	x
 }
 
+/// Returns the greatest common divisor of a and b
+pub const fn gcd(a: usize, b: usize) -> usize {
+	if b == 0 {
+		a
+	} else {
+		gcd(b, a % b)
+	}
+}
+
+/// Returns the least common multiple of a and b
+pub const fn lcm(a: usize, b: usize) -> usize {
+	a / gcd(a, b) * b
+}
+
 /// Return the k-th permutation of the given vector
 pub fn permute_vec<T>(v: Vec<T>, k: usize) -> Vec<T>
 where
@@ -110,7 +124,6 @@ pub fn get_kth_perm(n: usize, k: usize) -> Vec<usize> {
 ///
 /// Runtime: O(n)
 /// Additional Memory O(1)
-
 pub fn map_nck(v: &[bool]) -> usize {
 	let mut x = 0;
 	let mut k: usize = 0;
@@ -144,6 +157,21 @@ pub fn count_permutation_inversions(k: usize) -> usize {
 	x
 }
 
+/// Return the parity of the permutation (0 if it's even, 1 if it's odd).
+/// Unlike 'count_permutation_inversions', the values can be any distinct numbers.
+///
+/// Runtime O(n^2)
+/// Additional Memory O(1)
+pub fn permutation_parity(perm: &[usize]) -> usize {
+	let mut inversions = 0;
+	for i in 0..perm.len() {
+		for j in (i + 1)..perm.len() {
+			inversions += (perm[i] > perm[j]) as usize;
+		}
+	}
+	inversions % 2
+}
+
 /// Return a the i-th version of "n choose k".
 /// (Inverse of 'map_cnk')
 /// If n < k, or i >= n choose k the output is meaningless.
@@ -183,6 +211,7 @@ mod tests {
 
 	#[test]
 	/// Test that the mapping of the i-th permutation is correct
+	#[allow(clippy::needless_range_loop)]
 	fn permutation_mapping() {
 		for i in 0..9 {
 			for j in 0..FAC[i] {
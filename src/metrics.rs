@@ -0,0 +1,85 @@
+use crate::cube::turn::{Turn, TurnType, TurnWise};
+
+/// A way of counting the length of a sequence
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Default,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	strum::EnumIter,
+	strum::EnumString,
+	strum::Display,
+)]
+#[strum(serialize_all = "UPPERCASE", ascii_case_insensitive)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(rename_all = "UPPERCASE")
+)]
+pub enum Metric {
+	/// Half turn metric: Every turn of a side counts once, a slice counts as two sides
+	#[default]
+	Htm,
+	/// Quarter turn metric: Like the half turn metric, but half turns count twice
+	Qtm,
+	/// Slice turn metric: Every turn of a side or slice counts once
+	Stm,
+	/// Execution turn metric: Every turn counts once, including rotations
+	Etm,
+}
+
+impl Metric {
+	/// Return the cost of a single turn
+	pub const fn cost(&self, turn: Turn) -> usize {
+		let side = turn.side;
+		if side.is_rotation() {
+			return matches!(self, Metric::Etm) as usize;
+		}
+
+		let layers = if side.is_slice() { 2 } else { 1 };
+		match self {
+			Metric::Htm => layers,
+			Metric::Qtm => match turn.wise {
+				TurnWise::Double => 2 * layers,
+				_ => layers,
+			},
+			Metric::Stm | Metric::Etm => 1,
+		}
+	}
+
+	/// Return the length of the sequence
+	pub fn count(&self, turns: &[Turn]) -> usize {
+		turns.iter().map(|t| self.cost(*t)).sum()
+	}
+
+	/// Return the highest cost of a turn of a side.
+	/// A solver which only turns sides needs at most this many times
+	/// as long in this metric as in the half turn metric.
+	pub const fn max_side_cost(&self) -> usize {
+		self.cost(Turn {
+			side: TurnType::U,
+			wise: TurnWise::Double,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse_turns;
+
+	#[test]
+	/// Count a sequence with all kinds of turns in every metric
+	fn metric_count() {
+		let turns = parse_turns("R U2 M' E2 Rw2 x y2").unwrap();
+		assert_eq!(Metric::Htm.count(&turns), 7);
+		assert_eq!(Metric::Qtm.count(&turns), 11);
+		assert_eq!(Metric::Stm.count(&turns), 5);
+		assert_eq!(Metric::Etm.count(&turns), 7);
+	}
+}
@@ -0,0 +1,408 @@
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+	cube::{
+		cubiecube::{CubieCube, Ori, CORNER_ORI, CORNER_PERM, EDGE_ORI, EDGE_PERM},
+		sequence::Sequence,
+		turn::{Turn, TurnType, TurnWise},
+		Corner, Edge, RubiksCube,
+	},
+	math::{count_permutation_inversions, permutation_parity},
+	solve::{self, optimal, SolveError, SolveOptions},
+};
+
+/// Cubes which are solved in fewer turns are rejected (like the WCA does)
+pub const MIN_LENGTH: usize = 2;
+
+/// The largest min_length of a scramble. The distance of the cube is checked with a search
+/// of fewer turns, which must not need the pruning tables of the optimal solver.
+pub const MAX_MIN_LENGTH: usize = optimal::TABLE_FREE_LEN + 1;
+
+/// The number of random turns generating a cube of the <R,U> group
+const TWO_GEN_TURNS: usize = 200;
+
+// The positions of the pieces of each set, sorted
+const LL_CORNERS: &[Corner] = &[Corner::URF, Corner::UBR, Corner::ULB, Corner::UFL];
+const LL_EDGES: &[Edge] = &[Edge::UF, Edge::UR, Edge::UB, Edge::UL];
+// The F2L scramble also scrambles the last layer, so all edges except the cross ones
+const NON_CROSS_EDGES: &[Edge] = &[
+	Edge::UF,
+	Edge::UR,
+	Edge::UB,
+	Edge::UL,
+	Edge::FR,
+	Edge::BR,
+	Edge::BL,
+	Edge::FL,
+];
+const ALL_CORNERS: &[Corner] = &[
+	Corner::URF,
+	Corner::UBR,
+	Corner::DLF,
+	Corner::DFR,
+	Corner::ULB,
+	Corner::UFL,
+	Corner::DRB,
+	Corner::DBL,
+];
+const ALL_EDGES: &[Edge] = &[
+	Edge::UF,
+	Edge::UR,
+	Edge::UB,
+	Edge::UL,
+	Edge::DF,
+	Edge::DR,
+	Edge::DB,
+	Edge::DL,
+	Edge::FR,
+	Edge::BR,
+	Edge::BL,
+	Edge::FL,
+];
+
+/// All the reasons why no scramble could be generated
+#[derive(thiserror::Error, Debug)]
+pub enum ScrambleError {
+	#[error("The minimal length of a scramble can be at most {MAX_MIN_LENGTH}, but is {0}")]
+	MinLength(usize),
+	#[error("{0}")]
+	Solve(#[from] SolveError),
+}
+
+/// The set of cubes a scramble is chosen from
+#[derive(
+	Clone,
+	Copy,
+	Debug,
+	Default,
+	PartialEq,
+	Eq,
+	Hash,
+	strum::EnumIter,
+	strum::EnumString,
+	strum::Display,
+)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
+pub enum ScrambleType {
+	/// Any cube
+	#[default]
+	Random,
+	/// Only the last layer (the U side) is unsolved
+	LastLayer,
+	/// Only the orientation of the last layer is unsolved
+	Oll,
+	/// Only the permutation of the last layer is unsolved
+	Pll,
+	/// Everything but the cross (the D edges) is unsolved
+	F2l,
+	/// Only the edges are unsolved
+	Edges,
+	/// Only the corners are unsolved
+	Corners,
+	/// Cubes which can be reached with R and U turns (generated by a long random walk,
+	/// so only approximately uniform)
+	TwoGen,
+}
+
+impl ScrambleType {
+	/// Return a uniformly random cube of the set.
+	/// Cubes of the <R,U> group are the exception: they are generated by a long random
+	/// sequence of R and U turns, so they are only approximately uniform.
+	pub fn random_cube<R: Rng + ?Sized>(&self, rng: &mut R) -> CubieCube {
+		use ScrambleType::*;
+
+		match self {
+			Random => random_cube(rng),
+			LastLayer => random_subset(rng, LL_CORNERS, LL_EDGES, true, true),
+			Oll => random_subset(rng, LL_CORNERS, LL_EDGES, false, true),
+			Pll => random_subset(rng, LL_CORNERS, LL_EDGES, true, false),
+			F2l => random_subset(rng, ALL_CORNERS, NON_CROSS_EDGES, true, true),
+			Edges => random_subset(rng, &[], ALL_EDGES, true, true),
+			Corners => random_subset(rng, ALL_CORNERS, &[], true, true),
+			TwoGen => {
+				let mut cube = CubieCube::new();
+				for i in 0..TWO_GEN_TURNS {
+					let side = if i % 2 == 0 { TurnType::R } else { TurnType::U };
+					let wise = *[
+						TurnWise::Clockwise,
+						TurnWise::Double,
+						TurnWise::CounterClockwise,
+					]
+					.choose(rng)
+					.unwrap();
+					cube.apply_turn(Turn { side, wise });
+				}
+				cube
+			}
+		}
+	}
+}
+
+/// Return a cube, where the given corners and edges are randomly permuted among
+/// their positions and/or randomly oriented. All other pieces are solved.
+/// The positions must be sorted.
+fn random_subset<R: Rng + ?Sized>(
+	rng: &mut R,
+	corners: &[Corner],
+	edges: &[Edge],
+	permute: bool,
+	orient: bool,
+) -> CubieCube {
+	let mut cube = CubieCube::new();
+
+	if permute {
+		let mut cs = corners.to_vec();
+		let mut es = edges.to_vec();
+		cs.shuffle(rng);
+		es.shuffle(rng);
+
+		// The corners and edges must have the same parity (see `check_solvability`).
+		// The positions are sorted, so it's the parity of the order of the pieces.
+		let cperm: Vec<usize> = cs.iter().map(|c| *c as usize).collect();
+		let eperm: Vec<usize> = es.iter().map(|e| *e as usize).collect();
+		if permutation_parity(&cperm) != permutation_parity(&eperm) {
+			if es.len() >= 2 {
+				es.swap(0, 1);
+			} else {
+				cs.swap(0, 1);
+			}
+		}
+
+		for (pos, corner) in corners.iter().zip(cs) {
+			cube.corners[*pos as usize].0 = corner;
+		}
+		for (pos, edge) in edges.iter().zip(es) {
+			cube.edges[*pos as usize].0 = edge;
+		}
+	}
+
+	if orient {
+		// The last piece of each kind fixes the sum of the orientations
+		let mut sum = 0;
+		for (i, pos) in corners.iter().enumerate() {
+			let ori = if i + 1 < corners.len() {
+				rng.gen_range(0..3)
+			} else {
+				(3 - sum % 3) % 3
+			};
+			sum += ori;
+			cube.corners[*pos as usize].1 = ori as Ori;
+		}
+
+		let mut sum = 0;
+		for (i, pos) in edges.iter().enumerate() {
+			let ori = if i + 1 < edges.len() {
+				rng.gen_range(0..2)
+			} else {
+				sum % 2
+			};
+			sum += ori;
+			cube.edges[*pos as usize].1 = ori as Ori;
+		}
+	}
+
+	#[cfg(debug_assertions)]
+	assert!(cube.is_solvable());
+
+	cube
+}
+
+/// Return a uniformly random solvable cube
+pub fn random_cube<R: Rng + ?Sized>(rng: &mut R) -> CubieCube {
+	let mut cubie = CubieCube::new();
+
+	// Generate a cubie by setting random coordinates
+	cubie.set_edge_orientation(rng.gen_range(0..EDGE_ORI));
+	cubie.set_corner_orientation(rng.gen_range(0..CORNER_ORI));
+
+	let cperm = rng.gen_range(0..CORNER_PERM);
+	let mut eperm = rng.gen_range(0..EDGE_PERM);
+
+	// The number of swaps have to be even
+	// Which is equivalent to: The number of inversions has to be even.
+	let inv = count_permutation_inversions(cperm);
+	let inv2 = count_permutation_inversions(eperm);
+
+	if (inv + inv2) % 2 == 1 {
+		// It can be proven that the sum over all factoradic digits
+		// are the total number of inversions.
+		// Using the factoradic number system, we can simply change
+		// the second digit by one, which is determined by the first bit.
+		eperm ^= 1;
+	}
+
+	cubie.set_corner_permutation(cperm);
+	cubie.set_edge_permutation(eperm);
+
+	#[cfg(debug_assertions)]
+	assert!(cubie.is_solvable());
+
+	cubie
+}
+
+/// Return a sequence which turns the solved cube into the given cube.
+/// It's the inverted solution of the default solver, so it only turns the sides
+/// and contains no redundant turns.
+pub fn scramble_to(cube: &CubieCube) -> Result<Sequence, SolveError> {
+	let solution = solve::default_solver().solve(cube, &SolveOptions::default())?;
+	Ok(Sequence::from(solution.turns).inverse().simplify())
+}
+
+/// Return a random-state scramble of the given type, whose cube can't be solved
+/// in fewer than min_length turns. Random cubes are generated until one is far enough
+/// from the solved cube.
+///
+/// The distance is checked with the optimal solver, which searches the few turns below
+/// MAX_MIN_LENGTH without its pruning tables. Larger min_length are rejected.
+pub fn scramble<R: Rng + ?Sized>(
+	rng: &mut R,
+	kind: ScrambleType,
+	min_length: usize,
+) -> Result<Sequence, ScrambleError> {
+	if min_length > MAX_MIN_LENGTH {
+		return Err(ScrambleError::MinLength(min_length));
+	}
+
+	loop {
+		let cube = kind.random_cube(rng);
+		let scramble = scramble_to(&cube)?;
+
+		// The scramble is a solution, so it's never shorter than the distance
+		if scramble.len() >= min_length && !is_solvable_within(&cube, min_length)? {
+			return Ok(scramble);
+		}
+	}
+}
+
+/// Return true if the cube can be solved in fewer than length turns.
+/// The length must be at most MAX_MIN_LENGTH, so the search needs no pruning tables.
+fn is_solvable_within(cube: &CubieCube, length: usize) -> Result<bool, SolveError> {
+	debug_assert!(length <= MAX_MIN_LENGTH);
+	if length == 0 {
+		return Ok(false);
+	}
+
+	let options = SolveOptions {
+		max_length: Some(length - 1),
+		..SolveOptions::default()
+	};
+	match optimal::solve(cube, &options) {
+		Ok(_) => Ok(true),
+		Err(SolveError::NoSolution) => Ok(false),
+		Err(e) => Err(e),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::{rngs::StdRng, SeedableRng};
+	use strum::IntoEnumIterator;
+
+	use super::*;
+
+	#[test]
+	/// Random cubes are solvable and the same seed generates the same cubes
+	fn random_cubes() {
+		let mut rng = StdRng::seed_from_u64(42);
+		let mut rng2 = StdRng::seed_from_u64(42);
+
+		for _ in 0..100 {
+			let cube = random_cube(&mut rng);
+			assert!(cube.is_solvable());
+			assert_eq!(cube, random_cube(&mut rng2));
+		}
+	}
+
+	#[test]
+	/// The cubes of each scramble type only change the pieces of their subset
+	fn scramble_types() {
+		let mut rng = StdRng::seed_from_u64(42);
+		let solved = CubieCube::new();
+
+		for kind in ScrambleType::iter() {
+			for _ in 0..20 {
+				let cube = kind.random_cube(&mut rng);
+				assert!(cube.is_solvable(), "{kind}");
+
+				let unchanged_corners: &[Corner] = match kind {
+					ScrambleType::LastLayer | ScrambleType::Oll | ScrambleType::Pll => {
+						&[Corner::DLF, Corner::DFR, Corner::DRB, Corner::DBL]
+					}
+					ScrambleType::Edges => ALL_CORNERS,
+					ScrambleType::TwoGen => &[Corner::DLF, Corner::DBL],
+					_ => &[],
+				};
+				let unchanged_edges: &[Edge] = match kind {
+					ScrambleType::LastLayer | ScrambleType::Oll | ScrambleType::Pll => {
+						&[Edge::DF, Edge::DR, Edge::DB, Edge::DL, Edge::FR, Edge::BR]
+					}
+					ScrambleType::F2l => &[Edge::DF, Edge::DR, Edge::DB, Edge::DL],
+					ScrambleType::Corners => ALL_EDGES,
+					ScrambleType::TwoGen => &[Edge::DF, Edge::DB, Edge::DL, Edge::BL, Edge::FL],
+					_ => &[],
+				};
+				for c in unchanged_corners {
+					assert_eq!(cube.corner_at(*c), solved.corner_at(*c), "{kind}");
+				}
+				for e in unchanged_edges {
+					assert_eq!(cube.edge_at(*e), solved.edge_at(*e), "{kind}");
+				}
+
+				// PLL only permutes and OLL only orients the pieces
+				if kind == ScrambleType::Pll {
+					assert_eq!(cube.get_corner_orientation_coord(), 0);
+					assert_eq!(cube.get_edge_orientation_coord(), 0);
+				}
+				if kind == ScrambleType::Oll {
+					assert_eq!(cube.get_corner_perm_coord(), 0);
+					assert_eq!(cube.get_edge_permutation_coord(), 0);
+				}
+			}
+		}
+	}
+
+	#[test]
+	/// A scramble turns the solved cube into the scrambled cube
+	fn scramble_to_cube() {
+		let mut rng = StdRng::seed_from_u64(42);
+
+		for kind in ScrambleType::iter() {
+			let cube = kind.random_cube(&mut rng);
+			let mut scrambled = CubieCube::new();
+			for turn in scramble_to(&cube).unwrap().turns {
+				scrambled.apply_turn(turn);
+			}
+			assert_eq!(scrambled, cube, "{kind}");
+		}
+	}
+
+	#[test]
+	/// Scrambled cubes can't be solved in fewer than min_length turns
+	fn scramble_min_length() {
+		let mut rng = StdRng::seed_from_u64(42);
+
+		for min_length in 0..=MAX_MIN_LENGTH {
+			let scramble = scramble(&mut rng, ScrambleType::Pll, min_length).unwrap();
+			let mut cube = CubieCube::new();
+			for turn in scramble.turns {
+				cube.apply_turn(turn);
+			}
+			assert!(!is_solvable_within(&cube, min_length).unwrap());
+		}
+
+		// "R U R' U'" needs exactly 4 turns
+		let mut cube = CubieCube::new();
+		for turn in crate::parse_turns("R U R' U'").unwrap() {
+			cube.apply_turn(turn);
+		}
+		assert!(is_solvable_within(&cube, 5).unwrap());
+		assert!(!is_solvable_within(&cube, 4).unwrap());
+
+		assert!(is_solvable_within(&CubieCube::new(), 1).unwrap());
+		assert!(!is_solvable_within(&CubieCube::new(), 0).unwrap());
+		assert!(matches!(
+			scramble(&mut rng, ScrambleType::Random, MAX_MIN_LENGTH + 1),
+			Err(ScrambleError::MinLength(_))
+		));
+	}
+}
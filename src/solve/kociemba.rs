@@ -1,35 +1,74 @@
-use std::io::{Read, Result, Write};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::time::Instant;
 
 use lazy_static::lazy_static;
 use strum::IntoEnumIterator;
 
-use crate::{arraycube::ArrayCube, cubiecube::*, parse_turns, Edge, RubiksCube, Turn, NUM_EDGES};
+use crate::{
+	arraycube::ArrayCube, cubiecube::*, parse_turns, Edge, RubiksCube, Turn, TurnType, NUM_EDGES,
+};
 
 use crate::math::*;
 
+use super::{
+	stream_solutions,
+	tables::{self, Generator, PruningTable, Table, UNVISITED},
+	Solution, SolutionSet, Solutions, SolveError, SolveOptions, Solver,
+};
+
 /// v[coord][i] is the coordinate when applying move i on coord
-type Movetable = Vec<Vec<u16>>;
+pub(super) type Movetable = Vec<Vec<u16>>;
 
 /// v[coord][i] is the coordinate of the i-th symmetry of coord
-type Symtable = Vec<Vec<u16>>;
+pub(super) type Symtable = Vec<Vec<u16>>;
 
 /// v[symcoord][i] = (dst, sym) results by applying the move i on the symcoordinate "symcoord"
 /// where dst: Is the resulting sym-coordinate
 /// where sym: Is the sym-th symmetry of the to raw coordinate of symcoord
-type SymMovetable = Vec<Vec<(u16, u8)>>;
+pub(super) type SymMovetable = Vec<Vec<(u16, u8)>>;
 
 /// A mapper vector where v[i] is the raw coordinate of the
 /// representant cube of symmetry class i
-type SymToRawTable = Vec<u32>;
+pub(super) type SymToRawTable = Vec<u32>;
+
+/// v[raw] = (symcoord, sym) where symcoord is the symmetry class of the raw coordinate
+/// and sym the symmetry which maps the raw coordinate onto the representant of the class.
+/// (Inverse of SymToRawTable)
+pub(super) type RawToSymTable = Vec<(u16, u8)>;
 
 lazy_static! {
-	static ref cornersym: Symtable = gen_corner_ori_symtable();
-	static ref edgesym: Symtable = gen2_edge_perm_symtable();
-	static ref toraw: SymToRawTable = gen_symmetrytoraw();
-	static ref toraw2: SymToRawTable = gen2_symmetrytoraw();
-	static ref h1: Vec<u8> = gen_phase1_heuristics();
-	static ref h2: Vec<u8> = gen_phase2_heuristics();
-	static ref turns_phase1: Vec<Turn> =
+	pub(super) static ref cornersym: Symtable = gen_corner_ori_symtable();
+	static ref edgesym: Symtable = cached_rows(
+		"coords_edgesym.dat",
+		[EDGE8_PERM, 16],
+		gen2_edge_perm_symtable
+	);
+	pub(super) static ref toraw: SymToRawTable =
+		cached_symtoraw("coords_toraw.dat", SYM_LEN, gen_symmetrytoraw);
+	pub(super) static ref toraw2: SymToRawTable =
+		cached_symtoraw("coords_toraw2.dat", SYM2_LEN, gen2_symmetrytoraw);
+	pub(super) static ref toclass: RawToSymTable =
+		cached_rawtosym("coords_toclass.dat", EDGE_ORI * UDSLICE, gen_rawtosymmetry);
+	pub(super) static ref toclass2: RawToSymTable =
+		cached_rawtosym("coords_toclass2.dat", CORNER_PERM, gen2_rawtosymmetry);
+	pub(super) static ref twistmove: Movetable = gen_corner_ori_movetable();
+	pub(super) static ref flipmove: Movetable = gen_edge_ori_movetable();
+	pub(super) static ref udslicemove: Movetable = gen_udslice_movetable();
+	static ref cornermove2: Movetable = cached_rows(
+		"coords_cornermove2.dat",
+		[CORNER_PERM, 10],
+		gen2_corner_perm_movetable
+	);
+	static ref edgemove2: Movetable = cached_rows(
+		"coords_edgemove2.dat",
+		[EDGE8_PERM, 10],
+		gen2_edge_perm_movetable
+	);
+	static ref slicemove2: Movetable = gen2_udslice_perm_movetable();
+	static ref h1: PruningTable = gen_phase1_heuristics();
+	static ref h2: PruningTable = gen_phase2_heuristics();
+	pub(super) static ref turns_phase1: Vec<Turn> =
 		parse_turns("U U2 U' D D2 D' B B2 B' F F2 F' L L2 L' R R2 R'").unwrap();
 	static ref turns_phase2: Vec<Turn> = parse_turns("U U2 U' D D2 D' B2 F2 L2 R2").unwrap();
 	static ref rlslice: Vec<Edge> = vec![Edge::UF, Edge::DF, Edge::DB, Edge::UB];
@@ -37,14 +76,23 @@ lazy_static! {
 	static ref udslice: Vec<Edge> = vec![Edge::FR, Edge::BR, Edge::BL, Edge::FL];
 }
 
+/// The number of edges in the udslice
+const UDSLICE_EDGES: usize = 4;
+
 // Helper list to map the index of the edge in a slice
 const EDGE_SLICE_INDEX: [usize; NUM_EDGES] = [0, 0, 3, 3, 1, 1, 2, 2, 0, 1, 2, 3];
 
-const SYM_LEN: usize = 64430;
+pub(super) const SYM_LEN: usize = 64430;
 const UDSLICE: usize = 495;
 
 const EDGE8_PERM: usize = 40320;
-const SYM2_LEN: usize = 2768;
+pub(super) const SYM2_LEN: usize = 2768;
+pub(super) const UDSLICE_PERM: usize = 24;
+
+/// No phase 2 solution needs more turns than this
+const MAX_PHASE2_LEN: usize = 18;
+/// No phase 1 solution needs more turns than this
+const MAX_PHASE1_LEN: usize = 12;
 
 fn get_flipudslice_coord(cube: &CubieCube) -> usize {
 	let edge = cube.get_edge_orientation_coord();
@@ -102,6 +150,28 @@ fn cube_from_udslice_edge_idx(idx: usize) -> CubieCube {
 	cube
 }
 
+fn cube_from_udslice_idx(idx: usize) -> CubieCube {
+	cube_from_edge_pos_idx(udslice.to_vec(), idx)
+}
+
+/// Get the cube where the udslice edges are permuted by the idx-th permutation
+fn cube_from_udslice_perm(idx: usize) -> CubieCube {
+	let mut cube = CubieCube::new();
+	let slice = permute_vec(udslice.to_vec(), idx);
+	for (i, edge) in slice.into_iter().enumerate() {
+		cube.edges[NUM_EDGES - UDSLICE_EDGES + i].0 = edge;
+	}
+	cube
+}
+
+/// Get the cube with:
+/// - udslice coord = idx / UDSLICE_PERM
+/// - the order of the udslice edges = idx % UDSLICE_PERM
+fn cube_from_udslice_sorted_idx(idx: usize) -> CubieCube {
+	let slice = permute_vec(udslice.to_vec(), idx % UDSLICE_PERM);
+	cube_from_edge_pos_idx(slice, idx / UDSLICE_PERM)
+}
+
 fn cube_from_rlslice_idx(idx: usize) -> CubieCube {
 	let pos = idx / 24;
 	let ord = idx % 24;
@@ -118,7 +188,7 @@ fn cube_from_fbslice_idx(idx: usize) -> CubieCube {
 	cube_from_edge_pos_idx(slice, pos)
 }
 
-fn cube_from_corner_perm(coord: usize) -> CubieCube {
+pub(super) fn cube_from_corner_perm(coord: usize) -> CubieCube {
 	let mut cube = CubieCube::new();
 	cube.set_corner_permutation(coord);
 	cube
@@ -130,10 +200,62 @@ fn cube_from_edge_perm(coord: usize) -> CubieCube {
 	cube
 }
 
+// ===== Table Caching =====
+
+/// Load a table of u16 rows from the table directory, or generate and save it
+fn cached_rows(name: &str, dims: [usize; 2], generate: fn() -> Vec<Vec<u16>>) -> Vec<Vec<u16>> {
+	let bytes = tables::load_or_generate_bytes(name, [dims[0], 2 * dims[1]], || {
+		generate()
+			.iter()
+			.flatten()
+			.flat_map(|v| v.to_le_bytes())
+			.collect()
+	});
+
+	bytes
+		.chunks_exact(2 * dims[1])
+		.map(|row| {
+			row.chunks_exact(2)
+				.map(|v| u16::from_le_bytes([v[0], v[1]]))
+				.collect()
+		})
+		.collect()
+}
+
+/// Load a SymToRawTable from the table directory, or generate and save it
+fn cached_symtoraw(name: &str, len: usize, generate: fn() -> SymToRawTable) -> SymToRawTable {
+	let bytes = tables::load_or_generate_bytes(name, [len, 4], || {
+		generate().iter().flat_map(|v| v.to_le_bytes()).collect()
+	});
+
+	bytes
+		.chunks_exact(4)
+		.map(|v| u32::from_le_bytes(v.try_into().unwrap()))
+		.collect()
+}
+
+/// Load a RawToSymTable from the table directory, or generate and save it
+fn cached_rawtosym(name: &str, len: usize, generate: fn() -> RawToSymTable) -> RawToSymTable {
+	let bytes = tables::load_or_generate_bytes(name, [len, 3], || {
+		generate()
+			.iter()
+			.flat_map(|(class, sym)| {
+				let [a, b] = class.to_le_bytes();
+				[a, b, *sym]
+			})
+			.collect()
+	});
+
+	bytes
+		.chunks_exact(3)
+		.map(|v| (u16::from_le_bytes([v[0], v[1]]), v[2]))
+		.collect()
+}
+
 // ===== Table Generating =====
 
 /// Create a movetable
-fn create_movetable(
+pub(super) fn create_movetable(
 	num_states: usize,
 	moves: &[Turn],
 	to_idx: fn(&CubieCube) -> usize,
@@ -224,7 +346,7 @@ fn create_symtable(
 	out
 }
 
-fn create_sym_movetable(
+pub(super) fn create_sym_movetable(
 	symtoraw: &SymToRawTable,
 	from_idx: fn(usize) -> CubieCube,
 	to_idx: fn(&CubieCube) -> usize,
@@ -253,6 +375,35 @@ fn create_sym_movetable(
 	out
 }
 
+fn create_rawtosym_list(
+	num_states: usize,
+	symtoraw: &SymToRawTable,
+	to_idx: fn(&CubieCube) -> usize,
+	from_idx: fn(usize) -> CubieCube,
+) -> RawToSymTable {
+	let mut out = vec![(u16::MAX, 0); num_states];
+
+	for (symidx, raw) in symtoraw.iter().enumerate() {
+		let cube = from_idx(*raw as usize);
+
+		// Iterate in the same order as get_sym_class does, so that both
+		// choose the same symmetry for self-symmetric representants
+		for sym in 0..16 {
+			let csym = get_symmetry(&cube, sym);
+			let n = to_idx(&csym);
+
+			if out[n].0 == u16::MAX {
+				out[n] = (symidx as u16, sym as u8);
+			}
+		}
+	}
+
+	#[cfg(debug_assertions)]
+	assert!(out.iter().all(|(symidx, _)| *symidx != u16::MAX));
+
+	out
+}
+
 fn get_sym_class(
 	cube: &CubieCube,
 	symtoraw: &SymToRawTable,
@@ -279,7 +430,42 @@ fn gen_symmetrytoraw() -> SymToRawTable {
 	)
 }
 
-fn gen_symflipudslicetable() -> SymMovetable {
+fn gen_rawtosymmetry() -> RawToSymTable {
+	create_rawtosym_list(
+		EDGE_ORI * UDSLICE,
+		&toraw,
+		get_flipudslice_coord,
+		cube_from_udslice_edge_idx,
+	)
+}
+
+fn gen_edge_ori_movetable() -> Movetable {
+	fn get_edge_ori_idx(cube: &CubieCube) -> usize {
+		cube.get_edge_orientation_coord()
+	}
+
+	create_movetable(
+		EDGE_ORI,
+		&turns_phase1,
+		get_edge_ori_idx,
+		cube_from_edge_ori_idx,
+	)
+}
+
+fn gen_udslice_movetable() -> Movetable {
+	fn get_udslice_idx(cube: &CubieCube) -> usize {
+		cube.get_udslice_coord()
+	}
+
+	create_movetable(
+		UDSLICE,
+		&turns_phase1,
+		get_udslice_idx,
+		cube_from_udslice_idx,
+	)
+}
+
+pub(super) fn gen_symflipudslicetable() -> SymMovetable {
 	create_sym_movetable(
 		&toraw,
 		cube_from_udslice_edge_idx,
@@ -309,116 +495,194 @@ fn gen_corner_ori_symtable() -> Symtable {
 	create_symtable(CORNER_ORI, 16, get_corner_ori_idx, cube_from_corner_ori_idx)
 }
 
-fn gen_phase1_heuristics() -> Vec<u8> {
-	const DATA_PATH: &str = "data/heuristics.dat";
+/// Return the udslice sorted coordinate: the udslice coordinate times UDSLICE_PERM
+/// plus the order in which the udslice edges appear
+pub(super) fn get_udslice_sorted_coord(cube: &CubieCube) -> usize {
+	let ord: Vec<_> = cube
+		.edges
+		.iter()
+		.filter(|(e, _)| udslice.contains(e))
+		.map(|(e, _)| EDGE_SLICE_INDEX[*e as usize])
+		.collect();
+	cube.get_udslice_coord() * UDSLICE_PERM + map_permutation(&ord)
+}
 
-	if let Ok(h) = read_data::<{ SYM_LEN * CORNER_ORI }>(DATA_PATH) {
-		return h;
-	}
+pub(super) fn gen_udslice_sorted_movetable() -> Movetable {
+	create_movetable(
+		SLICE_COORD,
+		&turns_phase1,
+		get_udslice_sorted_coord,
+		cube_from_udslice_sorted_idx,
+	)
+}
 
-	println!("Must generate heuristics for phase 1, please wait...");
+pub(super) fn gen_udslice_sorted_symtable() -> Symtable {
+	create_symtable(
+		SLICE_COORD,
+		16,
+		get_udslice_sorted_coord,
+		cube_from_udslice_sorted_idx,
+	)
+}
 
-	let ctable = gen_corner_ori_movetable();
-	let stable = gen_symflipudslicetable();
+/// v[i] has the bit j set, if the symmetry j maps the representant
+/// of the flipUDslice symmetry class i onto itself
+pub(super) fn gen_flipudslice_symstate() -> Vec<u16> {
+	let mut out = vec![0; SYM_LEN];
 
-	// Used to analyze symmetries of stable
-	let symstate: Vec<u16> = {
-		let mut out = vec![0; SYM_LEN];
+	for (i, state) in out.iter_mut().enumerate() {
+		let raw = toraw[i] as usize;
+		let cube = cube_from_udslice_edge_idx(raw);
 
-		for i in 0..SYM_LEN {
-			let raw = toraw[i] as usize;
-			let cube = cube_from_udslice_edge_idx(raw);
+		for sym in 1..16 {
+			let csym = get_symmetry(&cube, sym);
+			let idx = get_flipudslice_coord(&csym);
 
-			for sym in 1..16 {
-				let csym = get_symmetry(&cube, sym);
-				let idx = get_flipudslice_coord(&csym);
+			if idx == raw {
+				*state |= 1 << sym;
+			}
+		}
+	}
 
-				if idx == raw {
-					out[i] |= 1 << sym;
+	out
+}
+
+/// Call f with consecutive chunks of 0..len, distributed over all available threads
+pub(super) fn parallel_chunks(len: usize, f: impl Fn(Range<usize>) + Sync) {
+	const CHUNK: usize = 1 << 16;
+
+	let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+	let next = AtomicUsize::new(0);
+
+	std::thread::scope(|s| {
+		for _ in 0..threads {
+			s.spawn(|| loop {
+				let start = next.fetch_add(CHUNK, Ordering::Relaxed);
+				if start >= len {
+					break;
 				}
-			}
+				f(start..std::cmp::min(start + CHUNK, len));
+			});
 		}
+	});
+}
 
-		out
-	};
+/// View the table as atomics, so several threads can fill it
+pub(super) fn as_atomic(table: &mut [u8]) -> &[AtomicU8] {
+	// SAFETY: AtomicU8 has the same size and alignment as u8,
+	// and the mutable borrow guarantees exclusive access
+	unsafe { &*(table as *mut [u8] as *const [AtomicU8]) }
+}
+
+/// Set the distance of an unvisited entry and return true,
+/// or return false if the entry was already visited
+fn visit(table: &[AtomicU8], idx: usize, dist: u8) -> bool {
+	// Most entries are already visited, which a plain load detects much faster
+	table[idx].load(Ordering::Relaxed) == UNVISITED
+		&& table[idx]
+			.compare_exchange(UNVISITED, dist, Ordering::Relaxed, Ordering::Relaxed)
+			.is_ok()
+}
+
+/// The number of turns needed to solve phase 1, indexed by the
+/// flipUDslice sym-coordinate times CORNER_ORI plus the twist
+pub(super) const PHASE1_TABLE: Table = Table {
+	name: "heuristics.dat",
+	description: "phase 1",
+	dims: [SYM_LEN, CORNER_ORI],
+	generate: Generator::Distances(create_phase1_heuristics),
+};
+
+fn gen_phase1_heuristics() -> PruningTable {
+	PHASE1_TABLE.load_or_generate()
+}
+
+fn create_phase1_heuristics(progress: &mut dyn FnMut(usize, usize)) -> Vec<u8> {
+	let ctable = gen_corner_ori_movetable();
+	let stable = gen_symflipudslicetable();
+
+	// Used to analyze symmetries of stable
+	let symstate = gen_flipudslice_symstate();
 
-	const UNVISITED: u8 = u8::MAX;
 	let mut out = vec![UNVISITED; SYM_LEN * CORNER_ORI];
 	out[0] = 0;
 
+	// Avoid dereferencing the lazy statics in the hot loops
+	let cornersyms: &Symtable = &cornersym;
+	let nturns = turns_phase1.len();
+
+	// Every layer only depends on the previous ones, so it can be filled in parallel
 	for m in 0..12 {
+		progress(m as usize, 12);
+		let table = as_atomic(&mut out);
+
 		if m <= 8 {
 			// Forward generate
-			for idx in 0..SYM_LEN * CORNER_ORI {
-				if out[idx] != m {
-					continue;
-				}
-
-				let twist0 = idx % CORNER_ORI;
-				let udslice0 = idx / CORNER_ORI;
+			parallel_chunks(SYM_LEN * CORNER_ORI, |range| {
+				for idx in range {
+					if table[idx].load(Ordering::Relaxed) != m {
+						continue;
+					}
 
-				for i in 0..turns_phase1.len() {
-					let (udslice_coord, sym) = stable[udslice0][i];
+					let twist0 = idx % CORNER_ORI;
+					let udslice0 = idx / CORNER_ORI;
 
-					let twist1 = ctable[twist0][i] as usize;
-					let twist = cornersym[twist1][sym as usize];
-					let dst = twist as usize + udslice_coord as usize * CORNER_ORI;
+					for i in 0..nturns {
+						let (udslice_coord, sym) = stable[udslice0][i];
 
-					if out[dst] != UNVISITED {
-						continue;
-					}
-					out[dst] = m + 1;
+						let twist1 = ctable[twist0][i] as usize;
+						let twist = cornersyms[twist1][sym as usize];
+						let dst = twist as usize + udslice_coord as usize * CORNER_ORI;
 
-					let state = symstate[udslice_coord as usize];
-					if state == 1 {
-						continue;
-					}
+						if !visit(table, dst, m + 1) {
+							continue;
+						}
 
-					for j in 1..16 {
-						if (state >> j) & 1 == 1 {
-							let ddx = cornersym[twist as usize][j as usize];
-							let dst = ddx as usize + udslice_coord as usize * CORNER_ORI;
+						let state = symstate[udslice_coord as usize];
+						if state == 1 {
+							continue;
+						}
 
-							if out[dst] != UNVISITED {
-								continue;
+						for j in 1..16 {
+							if (state >> j) & 1 == 1 {
+								let ddx = cornersyms[twist as usize][j as usize];
+								let dst = ddx as usize + udslice_coord as usize * CORNER_ORI;
+								visit(table, dst, m + 1);
 							}
-							out[dst] = m + 1;
 						}
 					}
 				}
-			}
+			});
 		} else {
 			// Backward search
-			for idx in 0..SYM_LEN * CORNER_ORI {
-				if out[idx] != UNVISITED {
-					continue;
-				}
+			parallel_chunks(SYM_LEN * CORNER_ORI, |range| {
+				for idx in range {
+					if table[idx].load(Ordering::Relaxed) != UNVISITED {
+						continue;
+					}
 
-				let twist0 = idx % CORNER_ORI;
-				let udslice0 = idx / CORNER_ORI;
+					let twist0 = idx % CORNER_ORI;
+					let udslice0 = idx / CORNER_ORI;
 
-				for i in 0..turns_phase1.len() {
-					let (udslice_coord, sym) = stable[udslice0][i];
+					for i in 0..nturns {
+						let (udslice_coord, sym) = stable[udslice0][i];
 
-					let twist1 = ctable[twist0][i] as usize;
-					let twist = cornersym[twist1][sym as usize];
-					let dst = twist as usize + udslice_coord as usize * CORNER_ORI;
+						let twist1 = ctable[twist0][i] as usize;
+						let twist = cornersyms[twist1][sym as usize];
+						let dst = twist as usize + udslice_coord as usize * CORNER_ORI;
 
-					if out[dst] != m {
-						continue;
+						if table[dst].load(Ordering::Relaxed) != m {
+							continue;
+						}
+						table[idx].store(m + 1, Ordering::Relaxed);
+						break;
 					}
-					out[idx] = m + 1;
-					break;
 				}
-			}
+			});
 		}
 	}
 
-	match save_data(DATA_PATH, &out) {
-		Ok(()) => {}
-		Err(_) => eprintln!("Could not save heuristics for phase 1!"),
-	}
-
+	progress(12, 12);
 	out
 }
 
@@ -437,6 +701,48 @@ fn gen2_symmetrytoraw() -> SymToRawTable {
 	)
 }
 
+fn gen2_rawtosymmetry() -> RawToSymTable {
+	fn get_corner_perm(cube: &CubieCube) -> usize {
+		cube.get_corner_perm_coord()
+	}
+
+	create_rawtosym_list(CORNER_PERM, &toraw2, get_corner_perm, cube_from_corner_perm)
+}
+
+fn gen2_corner_perm_movetable() -> Movetable {
+	fn get_corner_perm(cube: &CubieCube) -> usize {
+		cube.get_corner_perm_coord()
+	}
+
+	create_movetable(
+		CORNER_PERM,
+		&turns_phase2,
+		get_corner_perm,
+		cube_from_corner_perm,
+	)
+}
+
+fn gen2_udslice_perm_movetable() -> Movetable {
+	create_movetable(
+		UDSLICE_PERM,
+		&turns_phase2,
+		get_udslice_perm_coord,
+		cube_from_udslice_perm,
+	)
+}
+
+/// Return the permutation coordinate of the udslice edges.
+/// Only meaningful if all udslice edges are in the udslice.
+fn get_udslice_perm_coord(cube: &CubieCube) -> usize {
+	let perm: Vec<_> = cube
+		.edges
+		.iter()
+		.skip(NUM_EDGES - UDSLICE_EDGES)
+		.map(|(e, _)| *e as usize - (NUM_EDGES - UDSLICE_EDGES))
+		.collect();
+	map_permutation(&perm)
+}
+
 fn gen2_symmovetable() -> SymMovetable {
 	fn get_corn_perm(cube: &CubieCube) -> usize {
 		cube.get_corner_perm_coord()
@@ -468,17 +774,23 @@ fn gen2_edge_perm_symtable() -> Symtable {
 	create_symtable(EDGE8_PERM, 16, get_phase2_edge_perm, cube_from_edge_perm)
 }
 
-fn gen_phase2_heuristics() -> Vec<u8> {
-	const DATA_PATH: &str = "data/heuristics_phase2.dat";
-	if let Ok(data) = read_data::<{ SYM2_LEN * EDGE8_PERM }>(DATA_PATH) {
-		return data;
-	}
-	println!("Must generate heuristics for phase 2, please wait...");
+/// A lower bound of the number of turns needed to solve phase 2, indexed by the
+/// edge8 permutation coordinate times SYM2_LEN plus the corner sym-coordinate
+pub(super) const PHASE2_TABLE: Table = Table {
+	name: "heuristics_phase2.dat",
+	description: "phase 2",
+	dims: [EDGE8_PERM, SYM2_LEN],
+	generate: Generator::Distances(create_phase2_heuristics),
+};
+
+fn gen_phase2_heuristics() -> PruningTable {
+	PHASE2_TABLE.load_or_generate()
+}
 
+fn create_phase2_heuristics(progress: &mut dyn FnMut(usize, usize)) -> Vec<u8> {
 	let stable = gen2_symmovetable();
 	let etable = gen2_edge_perm_movetable();
 
-	const UNVISITED: u8 = u8::MAX;
 	let mut out = vec![UNVISITED; SYM2_LEN * EDGE8_PERM];
 	out[0] = 0;
 
@@ -502,163 +814,88 @@ fn gen_phase2_heuristics() -> Vec<u8> {
 		out
 	};
 
+	// Avoid dereferencing the lazy statics in the hot loops
+	let edgesyms: &Symtable = &edgesym;
+	let nturns = turns_phase2.len();
+
+	// Every layer only depends on the previous ones, so it can be filled in parallel
 	for m in 0..=18 {
+		progress(m as usize, 19);
+		let table = as_atomic(&mut out);
+
 		if m <= 12 {
 			// Forward generate
-			for idx in 0..SYM2_LEN * EDGE8_PERM {
-				if out[idx] != m {
-					continue;
-				}
-
-				let edge0 = idx / SYM2_LEN;
-				let corner0 = idx % SYM2_LEN;
+			parallel_chunks(SYM2_LEN * EDGE8_PERM, |range| {
+				for idx in range {
+					if table[idx].load(Ordering::Relaxed) != m {
+						continue;
+					}
 
-				for i in 0..10 {
-					let (corner, sym) = stable[corner0][i];
+					let edge0 = idx / SYM2_LEN;
+					let corner0 = idx % SYM2_LEN;
 
-					let edge1 = etable[edge0][i] as usize;
-					let edge = edgesym[edge1][sym as usize];
-					let dst = edge as usize * SYM2_LEN + corner as usize;
+					for i in 0..10 {
+						let (corner, sym) = stable[corner0][i];
 
-					if out[dst] != UNVISITED {
-						continue;
-					}
-					out[dst] = m + 1;
+						let edge1 = etable[edge0][i] as usize;
+						let edge = edgesyms[edge1][sym as usize];
+						let dst = edge as usize * SYM2_LEN + corner as usize;
 
-					let state = symstate[corner as usize];
-					if state == 1 {
-						continue;
-					}
+						if !visit(table, dst, m + 1) {
+							continue;
+						}
 
-					for j in 1..16 {
-						if (state >> j) & 1 == 1 {
-							let ddx = edgesym[edge as usize][j as usize];
-							let dst = ddx as usize * SYM2_LEN + corner as usize;
+						let state = symstate[corner as usize];
+						if state == 1 {
+							continue;
+						}
 
-							if out[dst] != UNVISITED {
-								continue;
+						for j in 1..16 {
+							if (state >> j) & 1 == 1 {
+								let ddx = edgesyms[edge as usize][j as usize];
+								let dst = ddx as usize * SYM2_LEN + corner as usize;
+								visit(table, dst, m + 1);
 							}
-							out[dst] = m + 1;
 						}
 					}
 				}
-			}
+			});
 		} else {
 			// Backward search
-			for idx in 0..SYM2_LEN * EDGE8_PERM {
-				if out[idx] != UNVISITED {
-					continue;
-				}
+			parallel_chunks(SYM2_LEN * EDGE8_PERM, |range| {
+				for idx in range {
+					if table[idx].load(Ordering::Relaxed) != UNVISITED {
+						continue;
+					}
 
-				let edge0 = idx / SYM2_LEN;
-				let corner0 = idx % SYM2_LEN;
+					let edge0 = idx / SYM2_LEN;
+					let corner0 = idx % SYM2_LEN;
 
-				for i in 0..turns_phase2.len() {
-					let (corner, sym) = stable[corner0][i];
+					for i in 0..nturns {
+						let (corner, sym) = stable[corner0][i];
 
-					let edge1 = etable[edge0][i] as usize;
-					let edge = edgesym[edge1][sym as usize];
-					let dst = edge as usize * SYM2_LEN + corner as usize;
+						let edge1 = etable[edge0][i] as usize;
+						let edge = edgesyms[edge1][sym as usize];
+						let dst = edge as usize * SYM2_LEN + corner as usize;
 
-					if out[dst] != m {
-						continue;
+						if table[dst].load(Ordering::Relaxed) != m {
+							continue;
+						}
+						table[idx].store(m + 1, Ordering::Relaxed);
+						break;
 					}
-					out[idx] = m + 1;
-					break;
 				}
-			}
+			});
 		}
 	}
 
-	match save_data(DATA_PATH, &out) {
-		Ok(()) => {}
-		Err(_) => eprintln!("Could not save heuristics for phase 2!"),
-	}
-
+	progress(19, 19);
 	out
 }
 
-fn get_phase2_coord(cube: &CubieCube) -> usize {
-	fn corner_perm(cube: &CubieCube) -> usize {
-		cube.get_corner_perm_coord()
-	}
-
-	let (z, sym) = get_sym_class(cube, &toraw2, corner_perm).unwrap();
-	let edge = cube.get_edge8_permutation_coord();
-
-	let y = edgesym[edge][sym] as usize;
-	y * SYM2_LEN + z
-}
-
 // ===== Solving =====
 
-fn save_data(filepath: &str, data: &[u8]) -> Result<()> {
-	let mut path = std::env::current_dir()?;
-	path.push(filepath);
-
-	let mut file = std::fs::File::create(path)?;
-	file.write_all(data)?;
-	Ok(())
-}
-
-fn read_data<const N: usize>(filepath: &str) -> Result<Vec<u8>> {
-	let mut path = std::env::current_dir()?;
-	path.push(filepath);
-
-	let mut file = std::fs::File::open(path)?;
-	let mut out = vec![0u8; N];
-	file.read_exact(&mut out)?;
-
-	Ok(out)
-}
-
-fn search_phase2(path: &mut Vec<Turn>, cube: CubieCube, g: usize, bound: usize) -> usize {
-	let f = g + h2[get_phase2_coord(&cube)] as usize;
-	if f > bound {
-		return f;
-	}
-
-	if cube.is_solved() {
-		return usize::MAX - 1;
-	}
-
-	let mut min = usize::MAX;
-
-	let mut ord: Vec<(u8, usize)> = vec![];
-	for (i, turn) in turns_phase2.iter().enumerate() {
-		let dst = {
-			let mut nc = cube.clone();
-			nc.apply_turn(*turn);
-			get_phase2_coord(&nc)
-		};
-
-		ord.push((h2[dst], i))
-	}
-
-	ord.sort();
-
-	for turn in ord.iter().map(|(_, i)| turns_phase2[*i]) {
-		let ncube = {
-			let mut nc = cube.clone();
-			nc.apply_turn(turn);
-			nc
-		};
-
-		path.push(turn);
-
-		let t = search_phase2(path, ncube, g + 1, bound);
-		if t == usize::MAX - 1 {
-			return usize::MAX - 1;
-		}
-		min = std::cmp::min(min, t);
-
-		path.pop();
-	}
-
-	min
-}
-
-const SLICE_COORD: usize = 495 * 24;
+pub(super) const SLICE_COORD: usize = 495 * 24;
 
 #[allow(dead_code)]
 fn gen_rlslice_sorted() -> Movetable {
@@ -746,69 +983,481 @@ fn gen_toedge8perm_table() -> Movetable {
 	out
 }
 
-pub fn solve(initial: ArrayCube) -> Option<Vec<Turn>> {
-	let mut turns = vec![];
-	let mut cube: CubieCube = initial
-		.clone()
-		.try_into()
-		.expect("The given cube couldn't be converted properly!");
+/// Return the index of h1 for the given phase 1 coordinates
+fn phase1_index(twist: usize, flip: usize, slice: usize) -> usize {
+	let (z, sym) = toclass[slice * EDGE_ORI + flip];
+	let y = cornersym[twist][sym as usize] as usize;
+	z as usize * CORNER_ORI + y
+}
 
-	let mut dist = {
-		let (z, sym) = get_sym_class(&cube, &toraw, get_flipudslice_coord).unwrap();
-		let y = cornersym[cube.get_corner_orientation_coord()][sym] as usize;
-		h1[z * CORNER_ORI + y]
-	};
+/// Return the number of turns needed to solve phase 1 for the given coordinates
+pub(super) fn phase1_dist(twist: usize, flip: usize, slice: usize) -> usize {
+	h1.dist(
+		(twist, flip, slice),
+		|(twist, flip, slice)| phase1_index(twist, flip, slice),
+		turns_phase1.len(),
+		|(twist, flip, slice), i| {
+			(
+				twistmove[twist][i] as usize,
+				flipmove[flip][i] as usize,
+				udslicemove[slice][i] as usize,
+			)
+		},
+	)
+}
+
+/// Return the number of turns needed to solve phase 1 for the given coordinates,
+/// which are one turn away from coordinates with the distance `near`.
+/// This is faster than `phase1_dist` for tables which only store the distance modulo 3.
+pub(super) fn phase1_dist_near(twist: usize, flip: usize, slice: usize, near: usize) -> usize {
+	h1.dist_near(phase1_index(twist, flip, slice), near)
+}
+
+/// Return the index of h2 for the given phase 2 coordinates
+fn phase2_index(corner: usize, edge8: usize) -> usize {
+	let (z, sym) = toclass2[corner];
+	let y = edgesym[edge8][sym as usize] as usize;
+	y * SYM2_LEN + z as usize
+}
+
+/// Return a lower bound of turns needed to solve phase 2 for the given coordinates
+fn phase2_dist(corner: usize, edge8: usize) -> usize {
+	h2.dist(
+		(corner, edge8),
+		|(corner, edge8)| phase2_index(corner, edge8),
+		turns_phase2.len(),
+		|(corner, edge8), i| {
+			(
+				cornermove2[corner][i] as usize,
+				edgemove2[edge8][i] as usize,
+			)
+		},
+	)
+}
+
+/// Return a lower bound of turns needed to solve phase 2 for the given coordinates,
+/// which are one turn away from coordinates with the distance `near`
+fn phase2_dist_near(corner: usize, edge8: usize, near: usize) -> usize {
+	h2.dist_near(phase2_index(corner, edge8), near)
+}
 
-	loop {
-		if dist == 0 {
-			break;
+/// Return true if a turn of the side 'next' may follow a turn of the side 'last'.
+/// Turning the same side twice is redundant and of two opposite sides
+/// (which commute) only one order is searched.
+pub(super) fn is_allowed_after(last: Option<TurnType>, next: TurnType) -> bool {
+	match last {
+		Some(last) => {
+			last != next && (last.axis() != next.axis() || (last as usize) < next as usize)
 		}
+		None => true,
+	}
+}
 
-		for turn in turns_phase1.iter() {
-			let mut dst = cube.clone();
-			dst.apply_turn(*turn);
+/// The state of the two-phase search
+struct Search<'a> {
+	cube: &'a CubieCube,
+	options: &'a SolveOptions,
+	start: Instant,
+	/// The current phase 1 sequence (indices of turns_phase1)
+	path1: Vec<usize>,
+	/// The current phase 2 sequence (indices of turns_phase2)
+	path2: Vec<usize>,
+	/// The solutions found so far
+	solutions: SolutionSet,
+	/// The number of solutions passed to on_solution
+	found: usize,
+	/// Called with every new solution, returns false if the search should stop
+	on_solution: &'a mut dyn FnMut(Vec<Turn>) -> bool,
+	/// Number of visited phase 1 nodes, used to poll the timeout
+	nodes: usize,
+	/// True if the search should stop
+	stop: bool,
+}
+
+impl<'a> Search<'a> {
+	fn new(
+		cube: &'a CubieCube,
+		options: &'a SolveOptions,
+		on_solution: &'a mut dyn FnMut(Vec<Turn>) -> bool,
+	) -> Self {
+		Self {
+			cube,
+			options,
+			start: Instant::now(),
+			path1: vec![],
+			path2: vec![],
+			solutions: SolutionSet::new(options.count, options.metric),
+			found: 0,
+			on_solution,
+			nodes: 0,
+			stop: false,
+		}
+	}
 
-			let (dz, sym) = get_sym_class(&dst, &toraw, get_flipudslice_coord).unwrap();
-			let dy = cornersym[dst.get_corner_orientation_coord()][sym] as usize;
+	fn run(&mut self) {
+		let twist = self.cube.get_corner_orientation_coord();
+		let flip = self.cube.get_edge_orientation_coord();
+		let slice = self.cube.get_udslice_coord();
+
+		// Iterate over phase 1 solutions of increasing length.
+		// Once phase 1 alone is as long as the bound, no shorter solution can be found.
+		let max_len = (MAX_PHASE1_LEN + MAX_PHASE2_LEN) * self.options.metric.max_side_cost();
+		let dist = phase1_dist(twist, flip, slice);
+		let mut depth = dist;
+		while !self.stop {
+			match self.solutions.bound() {
+				Some(bound) if bound <= depth => break,
+				None if depth > max_len => break,
+				_ => {}
+			}
 
-			let ddist = h1[dy + dz * CORNER_ORI];
+			self.search_phase1(twist, flip, slice, dist, depth);
+			depth += 1;
 
-			if ddist + 1 == dist {
-				cube.apply_turn(*turn);
-				turns.push(*turn);
-				dist = ddist;
+			// Without any limit, the search ends with the phase 1 depth of the first solutions.
+			// Their phase 2 may not start with the last side of phase 1, so shorter solutions
+			// of the same phase 1 depth often follow the first one.
+			if self.is_unlimited() && self.solutions.is_full() {
 				break;
 			}
 		}
 	}
 
-	// phase 2
+	/// Return the last turned side of phase 1
+	fn last_side1(&self) -> Option<TurnType> {
+		self.path1.last().map(|i| turns_phase1[*i].side)
+	}
+
+	/// Return true if the search is out of time and has a solution
+	fn timed_out(&self) -> bool {
+		match self.options.timeout {
+			Some(t) => !self.solutions.is_empty() && self.start.elapsed() >= t,
+			None => false,
+		}
+	}
+
+	/// Return true if the options neither limit the length nor the time of the search
+	fn is_unlimited(&self) -> bool {
+		self.options.max_length.is_none() && self.options.timeout.is_none()
+	}
+
+	/// Return true if the solutions found satisfy the maximal length in the options
+	fn is_satisfied(&self) -> bool {
+		self.solutions.is_full()
+			&& self
+				.options
+				.max_length
+				.is_some_and(|max| self.solutions.longest().is_some_and(|len| len <= max))
+	}
+
+	/// Return the length of the current phase 1 and phase 2 sequences
+	fn path_len(&self) -> (usize, usize) {
+		let metric = self.options.metric;
+		(
+			self.path1
+				.iter()
+				.map(|i| metric.cost(turns_phase1[*i]))
+				.sum(),
+			self.path2
+				.iter()
+				.map(|i| metric.cost(turns_phase2[*i]))
+				.sum(),
+		)
+	}
+
+	/// Return the maximal length of phase 2, so the total is shorter than the bound
+	fn max_len2(&self) -> Option<usize> {
+		let (len1, _) = self.path_len();
+		let max = MAX_PHASE2_LEN * self.options.metric.max_side_cost();
+		match self.solutions.bound() {
+			Some(bound) => bound
+				.checked_sub(len1 + 1)
+				.map(|bound| std::cmp::min(bound, max)),
+			None => Some(max),
+		}
+	}
+
+	/// Search phase 1 solutions of exactly togo turns from coordinates with the distance dist.
+	/// The turns are counted in the metric of the options, the distances in the half turn metric.
+	fn search_phase1(&mut self, twist: usize, flip: usize, slice: usize, dist: usize, togo: usize) {
+		if togo == 0 {
+			self.start_phase2();
+			return;
+		}
+
+		self.nodes += 1;
+		if self.nodes.is_multiple_of(4096) && (self.timed_out() || self.options.is_cancelled()) {
+			self.stop = true;
+			return;
+		}
+
+		let last = self.last_side1();
+		for (i, turn) in turns_phase1.iter().enumerate() {
+			if !is_allowed_after(last, turn.side) {
+				continue;
+			}
+
+			let cost = self.options.metric.cost(*turn);
+			if cost > togo {
+				continue;
+			}
+
+			let ntwist = twistmove[twist][i] as usize;
+			let nflip = flipmove[flip][i] as usize;
+			let nslice = udslicemove[slice][i] as usize;
+
+			let ndist = phase1_dist_near(ntwist, nflip, nslice, dist);
+			if ndist > togo - cost {
+				continue;
+			}
+
+			self.path1.push(i);
+			self.search_phase1(ntwist, nflip, nslice, ndist, togo - cost);
+			self.path1.pop();
+
+			if self.stop {
+				return;
+			}
+		}
+	}
+
+	/// Phase 1 is solved, now search the shortest phase 2 solutions within the bound
+	fn start_phase2(&mut self) {
+		// If the last turn is a phase 2 turn, the same solution
+		// is also found with a shorter phase 1.
+		if let Some(i) = self.path1.last() {
+			if turns_phase2.contains(&turns_phase1[*i]) {
+				return;
+			}
+		}
 
-	let mut bound = h2[get_phase2_coord(&cube)] as usize;
-	let mut path = vec![];
+		let mut cube = self.cube.clone();
+		for i in self.path1.iter() {
+			cube.apply_turn(turns_phase1[*i]);
+		}
 
-	loop {
-		let t = search_phase2(&mut path, cube.clone(), 0, bound);
-		if t == usize::MAX - 1 {
-			for p in path {
-				turns.push(p);
+		let corner = cube.get_corner_perm_coord();
+		let edge8 = cube.get_edge8_permutation_coord();
+		let slice = get_udslice_perm_coord(&cube);
+
+		// Only the phase 2 solutions of the shortest length are used,
+		// longer ones are found with other phase 1 solutions.
+		let last = self.last_side1();
+		let found = self.found;
+		let dist = phase2_dist(corner, edge8);
+		let mut depth = dist;
+		while !self.stop && self.found == found {
+			match self.max_len2() {
+				Some(max) if depth <= max => {}
+				_ => break,
 			}
-			break;
+
+			self.search_phase2(corner, edge8, slice, dist, depth, last);
+			depth += 1;
 		}
-		if t == usize::MAX {
-			return None;
+
+		if self.timed_out() {
+			self.stop = true;
 		}
-		bound = t;
 	}
 
-	#[cfg(debug_assertions)]
-	{
-		let mut c = initial.clone();
-		for turn in turns.iter() {
-			c.apply_turn(*turn);
+	/// Add the current path as solution
+	fn add_solution(&mut self) {
+		let turns: Vec<Turn> = self
+			.path1
+			.iter()
+			.map(|i| turns_phase1[*i])
+			.chain(self.path2.iter().map(|i| turns_phase2[*i]))
+			.collect();
+
+		#[cfg(debug_assertions)]
+		{
+			let mut c = self.cube.clone();
+			for turn in turns.iter() {
+				c.apply_turn(*turn);
+			}
+			assert!(c.is_solved());
+		}
+
+		if !self.solutions.insert(&turns) {
+			return;
+		}
+		self.found += 1;
+
+		if !(self.on_solution)(turns) || self.is_satisfied() {
+			self.stop = true;
 		}
-		assert!(c.is_solved());
 	}
 
-	Some(turns)
+	/// Search all phase 2 solutions of exactly togo turns from coordinates with the distance dist.
+	/// Return true if the search should be aborted.
+	fn search_phase2(
+		&mut self,
+		corner: usize,
+		edge8: usize,
+		slice: usize,
+		dist: usize,
+		togo: usize,
+		last: Option<TurnType>,
+	) -> bool {
+		if togo == 0 {
+			if corner == 0 && edge8 == 0 && slice == 0 {
+				self.add_solution();
+				// Further solutions of this length are only of interest below the bound
+				let (len1, len2) = self.path_len();
+				let len = len1 + len2;
+				return self.stop || self.solutions.bound().is_some_and(|bound| bound <= len);
+			}
+			return false;
+		}
+
+		for (i, turn) in turns_phase2.iter().enumerate() {
+			if !is_allowed_after(last, turn.side) {
+				continue;
+			}
+
+			let cost = self.options.metric.cost(*turn);
+			if cost > togo {
+				continue;
+			}
+
+			let ncorner = cornermove2[corner][i] as usize;
+			let nedge8 = edgemove2[edge8][i] as usize;
+			let nslice = slicemove2[slice][i] as usize;
+
+			let ndist = phase2_dist_near(ncorner, nedge8, dist);
+			if ndist > togo - cost {
+				continue;
+			}
+
+			self.path2.push(i);
+			let abort =
+				self.search_phase2(ncorner, nedge8, nslice, ndist, togo - cost, Some(turn.side));
+			self.path2.pop();
+
+			if abort {
+				return true;
+			}
+		}
+
+		false
+	}
+}
+
+/// Search solutions with the two-phase algorithm and pass every new solution to
+/// on_solution, as soon as it is found. The search stops if on_solution returns false.
+///
+/// Phase 1 solutions of increasing length are combined with the shortest phase 2
+/// solutions, until options.count solutions satisfy the limits in options.
+/// Without any limit, the search stops after the phase 1 length, with which options.count
+/// solutions were found.
+pub fn search(
+	cube: &CubieCube,
+	options: &SolveOptions,
+	on_solution: &mut dyn FnMut(Vec<Turn>) -> bool,
+) {
+	Search::new(cube, options, on_solution).run();
+}
+
+/// Solve the cube with the two-phase algorithm and return the shortest solution found.
+/// Without any limit in options, the shortest solution with the first phase 1 length,
+/// which solves the cube, is returned.
+pub fn solve_with_options(cube: &CubieCube, options: &SolveOptions) -> Option<Vec<Turn>> {
+	let options = SolveOptions {
+		count: 1,
+		..options.clone()
+	};
+
+	let mut best: Option<Vec<Turn>> = None;
+	search(cube, &options, &mut |turns| {
+		best = Some(turns);
+		true
+	});
+	best
+}
+
+/// Solve the cube with the two-phase algorithm without any limit
+pub fn solve(initial: ArrayCube) -> Option<Vec<Turn>> {
+	let cube: CubieCube = initial
+		.try_into()
+		.expect("The given cube couldn't be converted properly!");
+	solve_with_options(&cube, &SolveOptions::default())
+}
+
+/// Kociemba's two-phase algorithm
+pub struct Kociemba;
+
+impl Solver for Kociemba {
+	fn name(&self) -> &'static str {
+		"Kociemba"
+	}
+
+	fn description(&self) -> &'static str {
+		"Kociemba's two-phase algorithm, fast with short solutions"
+	}
+
+	fn solve(
+		&self,
+		cube: &CubieCube,
+		options: &SolveOptions,
+	) -> std::result::Result<Solution, SolveError> {
+		cube.check_solvability()?;
+		solve_with_options(cube, options)
+			.map(Solution::from)
+			.ok_or(SolveError::NoSolution)
+	}
+
+	fn solutions(
+		&self,
+		cube: &CubieCube,
+		options: &SolveOptions,
+	) -> std::result::Result<Solutions, SolveError> {
+		cube.check_solvability()?;
+
+		let cube = cube.clone();
+		Ok(stream_solutions(options, move |options, on_solution| {
+			let mut found = false;
+			search(&cube, options, &mut |turns| {
+				found = true;
+				on_solution(turns)
+			});
+			match found {
+				true => Ok(()),
+				false => Err(SolveError::NoSolution),
+			}
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	/// Without any limit, short scrambles are solved with at most as many turns
+	fn solve_short_scrambles() {
+		for scramble in [
+			"R",
+			"U2",
+			"R U",
+			"L F R'",
+			"F R' B2 L",
+			"R U R' U'",
+			"D' B L2 U",
+		] {
+			let turns = parse_turns(scramble).unwrap();
+			let mut cube = CubieCube::new();
+			for turn in turns.iter() {
+				cube.apply_turn(*turn);
+			}
+
+			let solution = solve_with_options(&cube, &SolveOptions::default()).unwrap();
+			assert!(solution.len() <= turns.len(), "{scramble}: {solution:?}");
+
+			for turn in solution {
+				cube.apply_turn(turn);
+			}
+			assert!(cube.is_solved(), "{scramble}");
+		}
+	}
 }
@@ -1,2 +1,458 @@
 pub mod kociemba;
+pub mod optimal;
+pub mod tables;
 pub mod thistlewhaite;
+
+use std::{
+	collections::{BTreeMap, HashSet},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		mpsc, Arc,
+	},
+	time::Duration,
+};
+
+use strum::IntoEnumIterator;
+
+use crate::{cubiecube::CubieCube, metrics::Metric, CubeError, Sequence, Turn};
+
+/// Options passed to a solver.
+/// A solver may ignore options which don't apply to its algorithm.
+///
+/// Solvers which can improve their solution keep searching until a solution with at most
+/// max_length turns is found or the timeout expires. Without either, they return the
+/// first good solution they find. Lengths are counted in the given metric.
+#[derive(Clone)]
+pub struct SolveOptions {
+	/// Stop once a solution has at most this many turns
+	pub max_length: Option<usize>,
+	/// Return the best solution found after this time
+	pub timeout: Option<Duration>,
+	/// Called by long running solvers to report their progress.
+	/// It may be called from another thread, e.g. by the search of `Solver::solutions`.
+	pub progress: Option<ProgressCallback>,
+	/// The number of distinct solutions searched by `Solver::solutions`
+	pub count: usize,
+	/// The metric in which solutions should be short
+	pub metric: Metric,
+	/// Long running searches stop soon after this flag is set, e.g. by another thread.
+	/// Dropping the stream of `Solver::solutions` sets it.
+	pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl SolveOptions {
+	/// Return true if the search was cancelled
+	pub(crate) fn is_cancelled(&self) -> bool {
+		self.cancel
+			.as_ref()
+			.is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+	}
+}
+
+impl std::fmt::Debug for SolveOptions {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("SolveOptions")
+			.field("max_length", &self.max_length)
+			.field("timeout", &self.timeout)
+			.field("progress", &self.progress.is_some())
+			.field("count", &self.count)
+			.field("metric", &self.metric)
+			.field("cancel", &self.cancel)
+			.finish()
+	}
+}
+
+impl Default for SolveOptions {
+	fn default() -> Self {
+		Self {
+			max_length: None,
+			timeout: None,
+			progress: None,
+			count: 1,
+			metric: Metric::default(),
+			cancel: None,
+		}
+	}
+}
+
+/// A callback which receives the progress of a search, e.g. to update a progress bar
+pub type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+/// The progress of a running search
+#[derive(Clone, Debug)]
+pub struct Progress {
+	/// The search depth
+	pub depth: usize,
+	/// True once the depth was searched completely, false while it is searched
+	pub completed: bool,
+	/// The number of visited nodes so far
+	pub nodes: usize,
+	/// The time since the search started
+	pub elapsed: Duration,
+}
+
+/// The result of a successful solve
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+	/// The sequence which solves the cube
+	pub turns: Vec<Turn>,
+}
+
+impl From<Vec<Turn>> for Solution {
+	fn from(turns: Vec<Turn>) -> Self {
+		Self { turns }
+	}
+}
+
+impl std::fmt::Display for Solution {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		for (i, turn) in self.turns.iter().enumerate() {
+			if i > 0 {
+				write!(f, " ")?;
+			}
+			turn.fmt(f)?;
+		}
+		Ok(())
+	}
+}
+
+/// A solution with the details of its search, e.g. to pass it on as JSON
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolutionRecord {
+	/// The name of the solver
+	pub algorithm: String,
+	/// The sequence which solves the cube
+	pub sequence: Sequence,
+	/// The length of the sequence in every metric
+	pub lengths: BTreeMap<Metric, usize>,
+	/// The seconds it took to find the solution
+	pub time: f64,
+}
+
+impl SolutionRecord {
+	pub fn new(algorithm: &str, solution: &Solution, time: Duration) -> Self {
+		Self {
+			algorithm: algorithm.to_string(),
+			sequence: Sequence::new(solution.turns.clone()),
+			lengths: Metric::iter()
+				.map(|metric| (metric, metric.count(&solution.turns)))
+				.collect(),
+			time: time.as_secs_f64(),
+		}
+	}
+}
+
+/// A stream of solutions, in the order they were found.
+/// If the search fails, the error is the last item.
+pub type Solutions = Box<dyn Iterator<Item = Result<Solution, SolveError>> + Send>;
+
+/// All the reasons why solving a cube could fail
+#[derive(thiserror::Error, Debug)]
+pub enum SolveError {
+	#[error("The given cube is not solvable: {0}")]
+	Unsolvable(#[from] CubeError),
+	#[error("The solver could not find a solution")]
+	NoSolution,
+	#[error("The solver ran out of time")]
+	Timeout,
+	#[error("There is no algorithm called '{0}'")]
+	UnknownAlgorithm(String),
+}
+
+/// A solving algorithm.
+///
+/// To add a new algorithm, implement this trait in its own file in `src/solve/`
+/// and add it to the list of solvers in this module.
+pub trait Solver: Sync {
+	/// The name of the algorithm, which is used to select it
+	fn name(&self) -> &'static str;
+
+	/// A short description of the algorithm
+	fn description(&self) -> &'static str;
+
+	/// Solve the given cube and return the solving sequence
+	fn solve(&self, cube: &CubieCube, options: &SolveOptions) -> Result<Solution, SolveError>;
+
+	/// Search up to options.count distinct solutions and yield them as soon as they are found.
+	/// Once that many solutions were found, only shorter ones are yielded.
+	///
+	/// Solvers which can't enumerate solutions yield the single solution of `solve`.
+	fn solutions(&self, cube: &CubieCube, options: &SolveOptions) -> Result<Solutions, SolveError> {
+		let solution = self.solve(cube, options)?;
+		Ok(Box::new(std::iter::once(Ok(solution))))
+	}
+
+	/// Return a sequence which turns the start into the goal
+	fn solve_to(
+		&self,
+		start: &CubieCube,
+		goal: &CubieCube,
+		options: &SolveOptions,
+	) -> Result<Solution, SolveError> {
+		self.solve(&relative_cube(start, goal)?, options)
+	}
+
+	/// Like `solutions`, but the solutions turn the start into the goal
+	fn solutions_to(
+		&self,
+		start: &CubieCube,
+		goal: &CubieCube,
+		options: &SolveOptions,
+	) -> Result<Solutions, SolveError> {
+		self.solutions(&relative_cube(start, goal)?, options)
+	}
+}
+
+/// Return the cube, whose solutions turn the start into the goal.
+/// If the solution is S, then start S = goal, so it's the cube goal^-1 start.
+fn relative_cube(start: &CubieCube, goal: &CubieCube) -> Result<CubieCube, SolveError> {
+	start.check_solvability()?;
+	goal.check_solvability()?;
+	Ok(goal.inverse().compose(start))
+}
+
+/// All available solvers. The first one is the default.
+const SOLVERS: &[&dyn Solver] = &[
+	&kociemba::Kociemba,
+	&thistlewhaite::Thistlewaite,
+	&optimal::Optimal,
+];
+
+/// Return all available solvers
+pub fn solvers() -> &'static [&'static dyn Solver] {
+	SOLVERS
+}
+
+/// Return the default solver
+pub fn default_solver() -> &'static dyn Solver {
+	SOLVERS[0]
+}
+
+/// Return the solver with the given name (ignoring the case)
+pub fn get_solver(name: &str) -> Result<&'static dyn Solver, SolveError> {
+	SOLVERS
+		.iter()
+		.find(|s| s.name().eq_ignore_ascii_case(name))
+		.copied()
+		.ok_or_else(|| SolveError::UnknownAlgorithm(name.to_string()))
+}
+
+/// Run a search with the options in its own thread and stream the solutions it passes
+/// to its callback, followed by its error if it fails.
+/// Dropping the stream sets the cancel flag of the options, which the search must poll.
+/// The callback also returns false once the stream was dropped.
+pub(crate) fn stream_solutions<F>(options: &SolveOptions, search: F) -> Solutions
+where
+	F: FnOnce(&SolveOptions, &mut dyn FnMut(Vec<Turn>) -> bool) -> Result<(), SolveError>
+		+ Send
+		+ 'static,
+{
+	let cancel = options.cancel.clone().unwrap_or_default();
+	let options = SolveOptions {
+		cancel: Some(cancel.clone()),
+		..options.clone()
+	};
+
+	let (tx, rx) = mpsc::channel();
+	std::thread::spawn(move || {
+		let result = search(&options, &mut |turns| {
+			tx.send(Ok(Solution::from(turns))).is_ok()
+		});
+		if let Err(e) = result {
+			// Nobody is interested in the error, if the stream was dropped
+			let _ = tx.send(Err(e));
+		}
+	});
+
+	Box::new(SolutionStream { rx, cancel })
+}
+
+/// The receiving end of `stream_solutions`, which cancels the search when it is dropped
+struct SolutionStream {
+	rx: mpsc::Receiver<Result<Solution, SolveError>>,
+	cancel: Arc<AtomicBool>,
+}
+
+impl Iterator for SolutionStream {
+	type Item = Result<Solution, SolveError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.rx.recv().ok()
+	}
+}
+
+impl Drop for SolutionStream {
+	fn drop(&mut self) {
+		self.cancel.store(true, Ordering::Relaxed);
+	}
+}
+
+/// Return the sequence in a canonical order, where consecutive turns of opposite
+/// sides (which commute, like U D and D U) are sorted by their side.
+fn canonical_form(turns: &[Turn]) -> Vec<Turn> {
+	let mut out = turns.to_vec();
+	for run in out.chunk_by_mut(|a, b| a.side.axis() == b.side.axis()) {
+		run.sort_by_key(|turn| turn.side as usize);
+	}
+	out
+}
+
+/// The distinct solutions found by a search, of which only the `count` shortest are kept.
+/// Solutions which only differ by the order of commuting turns are the same.
+pub(crate) struct SolutionSet {
+	count: usize,
+	metric: Metric,
+	seen: HashSet<Vec<Turn>>,
+	/// The sorted lengths of the kept solutions
+	lengths: Vec<usize>,
+}
+
+impl SolutionSet {
+	pub fn new(count: usize, metric: Metric) -> Self {
+		Self {
+			count: count.max(1),
+			metric,
+			seen: HashSet::new(),
+			lengths: vec![],
+		}
+	}
+
+	/// Return true if count solutions were found
+	pub fn is_full(&self) -> bool {
+		self.lengths.len() >= self.count
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.lengths.is_empty()
+	}
+
+	/// Return the length of the longest kept solution
+	pub fn longest(&self) -> Option<usize> {
+		self.lengths.last().copied()
+	}
+
+	/// Return the exclusive upper bound of the length of new solutions, if there is one
+	pub fn bound(&self) -> Option<usize> {
+		match self.is_full() {
+			true => self.longest(),
+			false => None,
+		}
+	}
+
+	/// Add the solution and return true, if it is new and shorter than the bound
+	pub fn insert(&mut self, turns: &[Turn]) -> bool {
+		let length = self.metric.count(turns);
+		if self.bound().is_some_and(|bound| length >= bound) {
+			return false;
+		}
+		if !self.seen.insert(canonical_form(turns)) {
+			return false;
+		}
+
+		let pos = self.lengths.partition_point(|len| *len <= length);
+		self.lengths.insert(pos, length);
+		self.lengths.truncate(self.count);
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	/// Every solver must be found by its name
+	fn solver_registry() {
+		for solver in solvers() {
+			let found = get_solver(&solver.name().to_lowercase()).unwrap();
+			assert_eq!(found.name(), solver.name());
+		}
+		assert!(get_solver("unknown").is_err());
+	}
+
+	#[test]
+	/// Dropping a stream cancels its search, and the error of a search ends its stream
+	fn solution_stream() {
+		let (tx, rx) = mpsc::channel();
+		let mut solutions =
+			stream_solutions(&SolveOptions::default(), move |options, on_solution| {
+				on_solution(vec![]);
+				while !options.is_cancelled() {
+					std::thread::yield_now();
+				}
+				tx.send(()).unwrap();
+				Ok(())
+			});
+		assert_eq!(solutions.next().unwrap().unwrap(), Solution::from(vec![]));
+		drop(solutions);
+		rx.recv_timeout(Duration::from_secs(10)).unwrap();
+
+		let solutions = stream_solutions(&SolveOptions::default(), |_, _| Err(SolveError::Timeout));
+		let items: Vec<_> = solutions.collect();
+		assert!(matches!(items[..], [Err(SolveError::Timeout)]));
+	}
+
+	#[test]
+	/// Solutions which only differ by commuting opposite turns are duplicates
+	fn solution_set_dedup() {
+		let mut set = SolutionSet::new(2, Metric::Htm);
+		assert!(set.insert(&crate::parse_turns("R U D F").unwrap()));
+		assert!(!set.insert(&crate::parse_turns("R D U F").unwrap()));
+		assert!(set.insert(&crate::parse_turns("R U F D F'").unwrap()));
+		assert_eq!(set.bound(), Some(5));
+		assert!(!set.insert(&crate::parse_turns("L U F D F'").unwrap()));
+		assert!(set.insert(&crate::parse_turns("L U").unwrap()));
+		assert_eq!(set.bound(), Some(4));
+	}
+
+	#[test]
+	/// The solutions of the relative cube turn the start into the goal
+	fn relative_cubes() {
+		let cube = |s: &str| {
+			let mut cube = CubieCube::new();
+			for turn in crate::parse_turns(s).unwrap() {
+				crate::RubiksCube::apply_turn(&mut cube, turn);
+			}
+			cube
+		};
+		let start = cube("R U F' D2");
+		let goal = cube("L2 D B R'");
+
+		// start S = goal, where S = start^-1 goal
+		let solution = cube("D2 F U' R' L2 D B R'");
+		let relative = relative_cube(&start, &goal).unwrap();
+		assert!(relative.compose(&solution).is_solved());
+		assert_eq!(start.compose(&solution), goal);
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	/// Cubes and solutions can be written to and read from JSON
+	fn json_roundtrip() {
+		let turns = crate::parse_turns("R U2 M' F'").unwrap();
+		let record =
+			SolutionRecord::new("Kociemba", &Solution::from(turns.clone()), Duration::ZERO);
+		let json = serde_json::to_string(&record).unwrap();
+		assert_eq!(
+			json,
+			r#"{"algorithm":"Kociemba","sequence":"R U2 M' F'","lengths":{"HTM":5,"QTM":6,"STM":4,"ETM":4},"time":0.0}"#
+		);
+		assert_eq!(
+			serde_json::from_str::<SolutionRecord>(&json).unwrap(),
+			record
+		);
+
+		let mut cube = crate::ArrayCube::default();
+		cube.apply_turns(turns);
+		let json = serde_json::to_string(&cube).unwrap();
+		assert_eq!(
+			serde_json::from_str::<crate::ArrayCube>(&json).unwrap(),
+			cube
+		);
+
+		let cubie = CubieCube::try_from(cube).unwrap();
+		let json = serde_json::to_string(&cubie).unwrap();
+		assert_eq!(serde_json::from_str::<CubieCube>(&json).unwrap(), cubie);
+	}
+}
@@ -0,0 +1,802 @@
+use std::{
+	sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+	time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+
+use crate::{cubiecube::*, RubiksCube, Turn, TurnType};
+
+use super::kociemba::{
+	as_atomic, cornersym, create_movetable, create_sym_movetable, cube_from_corner_perm, flipmove,
+	gen_flipudslice_symstate, gen_symflipudslicetable, gen_udslice_sorted_movetable,
+	gen_udslice_sorted_symtable, get_udslice_sorted_coord, is_allowed_after, parallel_chunks,
+	toclass, toclass2, toraw, toraw2, turns_phase1, twistmove, Movetable, SymMovetable, Symtable,
+	SYM2_LEN, SYM_LEN, UDSLICE_PERM,
+};
+use super::{
+	stream_solutions,
+	tables::{Encoding, Generator, PruningTable, Table},
+	Progress, Solution, SolutionSet, Solutions, SolveError, SolveOptions, Solver,
+};
+
+/*
+ * The optimal solver runs IDA* over all 18 face turns.
+ *
+ * As heuristic it uses the maximum of
+ * - the distance in the huge table: the phase 1 coordinates of Kociemba's algorithm
+ *   (flipUDslice sym-coordinate x twist) together with the order of the UDslice edges,
+ *   looked up from all three axes by conjugating the cube with the URF3 symmetry
+ * - the distance of the corners (corner permutation sym-coordinate x twist)
+ *
+ * Both are lower bounds of the distance to the solved cube.
+ * The huge table has 3.4 billion entries, so it's generated and stored in the mod 3 encoding
+ * (845MB). Like Kociemba's optimal solver, it is generated by a breadth first search
+ * which switches to a backward search once most entries are visited.
+ */
+
+/// The symmetries which rotate the UD-axis onto the other two axes (URF3 and URF3^2)
+const AXIS_SYMMETRIES: [usize; 3] = [0, 16, 32];
+
+/// God's number in the half turn metric
+const MAX_LEN: usize = 20;
+
+/// Searches of at most this many turns don't need the pruning tables
+pub const TABLE_FREE_LEN: usize = 5;
+
+/// How often the progress is reported while a depth is searched
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+lazy_static! {
+	static ref cornermove: Movetable = gen_corner_perm_movetable();
+	static ref slicesortedmove: Movetable = gen_udslice_sorted_movetable();
+	static ref slicesortedsym: Symtable = gen_udslice_sorted_symtable();
+	static ref hc: PruningTable = gen_corner_heuristics();
+	static ref hh: PruningTable = gen_huge_heuristics();
+	static ref axismove: [Vec<usize>; 3] = gen_axis_movetable();
+}
+
+fn gen_corner_perm_movetable() -> Movetable {
+	fn get_corner_perm(cube: &CubieCube) -> usize {
+		cube.get_corner_perm_coord()
+	}
+
+	create_movetable(
+		CORNER_PERM,
+		&turns_phase1,
+		get_corner_perm,
+		cube_from_corner_perm,
+	)
+}
+
+fn gen_corner_perm_symmovetable() -> SymMovetable {
+	fn get_corner_perm(cube: &CubieCube) -> usize {
+		cube.get_corner_perm_coord()
+	}
+
+	create_sym_movetable(
+		&toraw2,
+		cube_from_corner_perm,
+		get_corner_perm,
+		&turns_phase1,
+	)
+}
+
+/// v[axis][i] is the index of the turn i, when the cube is seen from the given axis.
+/// That is, the conjugation of the turn i with AXIS_SYMMETRIES[axis].
+fn gen_axis_movetable() -> [Vec<usize>; 3] {
+	let turn_cubes: Vec<CubieCube> = turns_phase1
+		.iter()
+		.map(|turn| {
+			let mut cube = CubieCube::new();
+			cube.apply_turn(*turn);
+			cube
+		})
+		.collect();
+
+	AXIS_SYMMETRIES.map(|sym| {
+		turn_cubes
+			.iter()
+			.map(|cube| {
+				let conj = get_symmetry(cube, sym);
+				turn_cubes.iter().position(|c| *c == conj).unwrap()
+			})
+			.collect()
+	})
+}
+
+/// The number of turns needed to solve the corners, indexed by
+/// the symmetry class of the corner permutation times CORNER_ORI plus the twist.
+pub(super) const CORNER_TABLE: Table = Table {
+	name: "heuristics_corners.dat",
+	description: "the corners",
+	dims: [SYM2_LEN, CORNER_ORI],
+	generate: Generator::Distances(create_corner_heuristics),
+};
+
+/// The maximal number of turns needed to solve the corners.
+/// Only used to report the progress, the generation runs until no entry changes.
+const CORNER_DEPTH: u8 = 11;
+
+fn gen_corner_heuristics() -> PruningTable {
+	CORNER_TABLE.load_or_generate()
+}
+
+fn create_corner_heuristics(progress: &mut dyn FnMut(usize, usize)) -> Vec<u8> {
+	let stable = gen_corner_perm_symmovetable();
+
+	// Used to analyze symmetries of stable
+	let symstate: Vec<u16> = {
+		let mut out = vec![0; SYM2_LEN];
+
+		for (i, state) in out.iter_mut().enumerate() {
+			let raw = toraw2[i] as usize;
+			let cube = cube_from_corner_perm(raw);
+
+			for sym in 1..16 {
+				let csym = get_symmetry(&cube, sym);
+				if csym.get_corner_perm_coord() == raw {
+					*state |= 1 << sym;
+				}
+			}
+		}
+
+		out
+	};
+
+	const UNVISITED: u8 = u8::MAX;
+	let mut out = vec![UNVISITED; SYM2_LEN * CORNER_ORI];
+	out[0] = 0;
+
+	let mut m = 0;
+	let mut changed = true;
+	while changed {
+		changed = false;
+		progress(m.min(CORNER_DEPTH) as usize, CORNER_DEPTH as usize);
+
+		for idx in 0..SYM2_LEN * CORNER_ORI {
+			if out[idx] != m {
+				continue;
+			}
+
+			let twist0 = idx % CORNER_ORI;
+			let corner0 = idx / CORNER_ORI;
+
+			for i in 0..turns_phase1.len() {
+				let (corner, sym) = stable[corner0][i];
+
+				let twist1 = twistmove[twist0][i] as usize;
+				let twist = cornersym[twist1][sym as usize] as usize;
+				let corner = corner as usize;
+
+				let dst = corner * CORNER_ORI + twist;
+				if out[dst] == UNVISITED {
+					out[dst] = m + 1;
+					changed = true;
+				}
+
+				// Self-symmetric permutations have several equivalent twists
+				let state = symstate[corner];
+				for j in 1..16 {
+					if (state >> j) & 1 == 1 {
+						let dst = corner * CORNER_ORI + cornersym[twist][j] as usize;
+						if out[dst] == UNVISITED {
+							out[dst] = m + 1;
+							changed = true;
+						}
+					}
+				}
+			}
+		}
+
+		m += 1;
+	}
+
+	progress(CORNER_DEPTH as usize, CORNER_DEPTH as usize);
+	out
+}
+
+/// The number of turns needed to solve phase 1 and to sort the UDslice edges, indexed by
+/// (the flipUDslice sym-coordinate times UDSLICE_PERM plus the order of the UDslice edges)
+/// times CORNER_ORI plus the twist
+pub(super) const HUGE_TABLE: Table = Table {
+	name: "heuristics_optimal.dat",
+	description: "the optimal solver",
+	dims: [SYM_LEN * UDSLICE_PERM, CORNER_ORI],
+	generate: Generator::Mod3(create_huge_heuristics),
+};
+
+/// The maximal number of turns needed to solve the entries of the huge table.
+/// Only used to report the progress, the generation runs until all entries are visited.
+const HUGE_DEPTH: usize = 12;
+
+/// The value of unvisited entries in the mod 3 encoding
+const MOD3_UNVISITED: u8 = 3;
+
+fn gen_huge_heuristics() -> PruningTable {
+	HUGE_TABLE.load_or_generate()
+}
+
+/// Return the index of the huge table for the UDslice sorted coordinate `sorted`
+/// and the twist, both seen from the representant of the flipUDslice symmetry class
+fn huge_entry(class: usize, sorted: usize, twist: usize) -> usize {
+	(class * UDSLICE_PERM + sorted % UDSLICE_PERM) * CORNER_ORI + twist
+}
+
+/// Return the stored value of an entry of a table in the mod 3 encoding
+fn get_mod3(table: &[AtomicU8], idx: usize) -> u8 {
+	(table[idx / 4].load(Ordering::Relaxed) >> (2 * (idx % 4))) & 3
+}
+
+/// Set the value of an unvisited entry of a table in the mod 3 encoding and return true,
+/// or return false if the entry was already visited
+fn visit_mod3(table: &[AtomicU8], idx: usize, value: u8) -> bool {
+	let shift = 2 * (idx % 4);
+	if get_mod3(table, idx) != MOD3_UNVISITED {
+		return false;
+	}
+
+	// Clearing the bits, which are not set in value, turns the unvisited entry into value
+	let old = table[idx / 4].fetch_and(!((MOD3_UNVISITED ^ value) << shift), Ordering::Relaxed);
+	(old >> shift) & 3 == MOD3_UNVISITED
+}
+
+fn create_huge_heuristics(progress: &mut dyn FnMut(usize, usize)) -> PruningTable {
+	let stable = gen_symflipudslicetable();
+	let symstate = gen_flipudslice_symstate();
+
+	// Avoid dereferencing the lazy statics in the hot loops
+	let toraws: &[u32] = &toraw;
+	let twists: &Movetable = &twistmove;
+	let cornersyms: &Symtable = &cornersym;
+	let slicemoves: &Movetable = &slicesortedmove;
+	let slicesyms: &Symtable = &slicesortedsym;
+	let nturns = turns_phase1.len();
+
+	let len = SYM_LEN * UDSLICE_PERM * CORNER_ORI;
+	let mut out = vec![u8::MAX; Encoding::Mod3.size(len)];
+	let table = as_atomic(&mut out);
+	visit_mod3(table, 0, 0);
+
+	// Call f with the entry after each turn from the entry idx and the turn, until f returns true
+	let neighbours = |idx: usize, f: &mut dyn FnMut(usize, usize, usize) -> bool| {
+		let twist0 = idx % CORNER_ORI;
+		let class0 = idx / CORNER_ORI / UDSLICE_PERM;
+		let slice0 = toraws[class0] as usize / EDGE_ORI;
+		let sorted0 = slice0 * UDSLICE_PERM + idx / CORNER_ORI % UDSLICE_PERM;
+
+		for i in 0..nturns {
+			let (class, sym) = stable[class0][i];
+			let twist = cornersyms[twists[twist0][i] as usize][sym as usize];
+			let sorted = slicesyms[slicemoves[sorted0][i] as usize][sym as usize];
+			if f(class as usize, sorted as usize, twist as usize) {
+				return;
+			}
+		}
+	};
+
+	let mut visited = 1;
+	let mut frontier = 1;
+	let mut depth = 0;
+	while visited < len {
+		progress(depth.min(HUGE_DEPTH), HUGE_DEPTH);
+
+		let (current, next) = ((depth % 3) as u8, ((depth + 1) % 3) as u8);
+		// Searching backward from the unvisited entries is faster, once they are few
+		let backward = len - visited < 3 * frontier;
+		let found = AtomicUsize::new(0);
+
+		parallel_chunks(len, |range| {
+			let mut count = 0;
+
+			for idx in range {
+				let value = get_mod3(table, idx);
+
+				if backward {
+					if value != MOD3_UNVISITED {
+						continue;
+					}
+
+					// The neighbours of unvisited entries are at least `depth` turns away,
+					// so an entry with the value of the current depth is exactly that far
+					neighbours(idx, &mut |class, sorted, twist| {
+						let found = get_mod3(table, huge_entry(class, sorted, twist)) == current;
+						if found && visit_mod3(table, idx, next) {
+							count += 1;
+						}
+						found
+					});
+					continue;
+				}
+
+				// Entries which are 3 turns closer have the same value,
+				// but their neighbours are all visited
+				if value != current {
+					continue;
+				}
+
+				neighbours(idx, &mut |class, sorted, twist| {
+					count += visit_mod3(table, huge_entry(class, sorted, twist), next) as usize;
+
+					// Self-symmetric classes have several equivalent entries
+					let state = symstate[class];
+					for j in 1..16 {
+						if (state >> j) & 1 == 1 {
+							let sorted = slicesyms[sorted][j] as usize;
+							let twist = cornersyms[twist][j] as usize;
+							count +=
+								visit_mod3(table, huge_entry(class, sorted, twist), next) as usize;
+						}
+					}
+					false
+				});
+			}
+
+			found.fetch_add(count, Ordering::Relaxed);
+		});
+
+		frontier = found.into_inner();
+		visited += frontier;
+		depth += 1;
+
+		if frontier == 0 {
+			break;
+		}
+	}
+
+	progress(HUGE_DEPTH, HUGE_DEPTH);
+	PruningTable::from_bytes(out, len, Encoding::Mod3)
+}
+
+/// The coordinates of the cube needed for the heuristic
+#[derive(Clone, Copy)]
+struct Coords {
+	/// (twist, flip, UDslice sorted) of the cube seen from each axis
+	axes: [(usize, usize, usize); 3],
+	corner: usize,
+	/// The distance in the huge table seen from each axis
+	axis_dists: [usize; 3],
+	/// The distance of the corners
+	corner_dist: usize,
+}
+
+impl Coords {
+	fn new(cube: &CubieCube) -> Self {
+		let axes = AXIS_SYMMETRIES.map(|sym| {
+			let c = get_symmetry(cube, sym);
+			(
+				c.get_corner_orientation_coord(),
+				c.get_edge_orientation_coord(),
+				get_udslice_sorted_coord(&c),
+			)
+		});
+
+		let corner = cube.get_corner_perm_coord();
+		let (twist, _, _) = axes[0];
+
+		Self {
+			axes,
+			corner,
+			axis_dists: axes.map(|(twist, flip, sorted)| huge_dist(twist, flip, sorted)),
+			corner_dist: corner_dist(corner, twist),
+		}
+	}
+
+	/// Return the coordinates after applying the turn with index i, or None if the
+	/// resulting cube can't be solved in less than bound turns.
+	/// The heuristics are evaluated one after another, so most turns are discarded early.
+	fn apply_within(&self, i: usize, bound: usize) -> Option<Self> {
+		let mut out = *self;
+
+		for (axis, (twist, flip, sorted)) in out.axes.iter_mut().enumerate() {
+			let m = axismove[axis][i];
+			*twist = twistmove[*twist][m] as usize;
+			*flip = flipmove[*flip][m] as usize;
+			*sorted = slicesortedmove[*sorted][m] as usize;
+
+			if axis == 0 {
+				out.corner = cornermove[self.corner][i] as usize;
+				out.corner_dist = corner_dist_near(out.corner, *twist, self.corner_dist);
+				if out.corner_dist >= bound {
+					return None;
+				}
+			}
+
+			out.axis_dists[axis] = huge_dist_near(*twist, *flip, *sorted, self.axis_dists[axis]);
+			if out.axis_dists[axis] >= bound {
+				return None;
+			}
+		}
+
+		Some(out)
+	}
+
+	/// Return a lower bound of the number of turns needed to solve the cube
+	fn dist(&self) -> usize {
+		self.axis_dists
+			.into_iter()
+			.fold(self.corner_dist, std::cmp::max)
+	}
+}
+
+/// Return the index of hh for the given coordinates
+fn huge_index(twist: usize, flip: usize, sorted: usize) -> usize {
+	let (class, sym) = toclass[sorted / UDSLICE_PERM * EDGE_ORI + flip];
+	let twist = cornersym[twist][sym as usize] as usize;
+	let sorted = slicesortedsym[sorted][sym as usize] as usize;
+	huge_entry(class as usize, sorted, twist)
+}
+
+/// Return the number of turns needed to solve phase 1 and sort the UDslice edges
+fn huge_dist(twist: usize, flip: usize, sorted: usize) -> usize {
+	hh.dist(
+		(twist, flip, sorted),
+		|(twist, flip, sorted)| huge_index(twist, flip, sorted),
+		turns_phase1.len(),
+		|(twist, flip, sorted), i| {
+			(
+				twistmove[twist][i] as usize,
+				flipmove[flip][i] as usize,
+				slicesortedmove[sorted][i] as usize,
+			)
+		},
+	)
+}
+
+/// Return the number of turns needed to solve phase 1 and sort the UDslice edges
+/// for coordinates, which are one turn away from coordinates with the distance `near`
+fn huge_dist_near(twist: usize, flip: usize, sorted: usize, near: usize) -> usize {
+	hh.dist_near(huge_index(twist, flip, sorted), near)
+}
+
+/// Return the index of hc for the given corner coordinates
+fn corner_index(corner: usize, twist: usize) -> usize {
+	let (z, sym) = toclass2[corner];
+	let y = cornersym[twist][sym as usize] as usize;
+	z as usize * CORNER_ORI + y
+}
+
+/// Return the number of turns needed to solve the corners
+fn corner_dist(corner: usize, twist: usize) -> usize {
+	hc.dist(
+		(corner, twist),
+		|(corner, twist)| corner_index(corner, twist),
+		turns_phase1.len(),
+		|(corner, twist), i| (cornermove[corner][i] as usize, twistmove[twist][i] as usize),
+	)
+}
+
+/// Return the number of turns needed to solve the corners,
+/// which are one turn away from corners with the distance `near`
+fn corner_dist_near(corner: usize, twist: usize, near: usize) -> usize {
+	hc.dist_near(corner_index(corner, twist), near)
+}
+
+/// The state of the IDA* search
+struct Search<'a> {
+	cube: &'a CubieCube,
+	options: &'a SolveOptions,
+	start: Instant,
+	/// The current sequence (indices of turns_phase1)
+	path: Vec<usize>,
+	/// The solutions found so far
+	solutions: SolutionSet,
+	/// Called with every new solution, returns false if the search should stop
+	on_solution: &'a mut dyn FnMut(Vec<Turn>) -> bool,
+	/// The depth which is searched
+	depth: usize,
+	nodes: usize,
+	/// The time of the last progress report
+	reported: Instant,
+	timed_out: bool,
+	/// True if the search should stop
+	stop: bool,
+}
+
+impl Search<'_> {
+	/// Report the progress of the search
+	fn report(&mut self, completed: bool) {
+		if let Some(progress) = &self.options.progress {
+			progress(&Progress {
+				depth: self.depth,
+				completed,
+				nodes: self.nodes,
+				elapsed: self.start.elapsed(),
+			});
+			self.reported = Instant::now();
+		}
+	}
+
+	/// Return true if the search exceeded the timeout or was cancelled.
+	/// Every few nodes this also reports the progress within the depth.
+	fn check_timeout(&mut self) -> bool {
+		if self.nodes.is_multiple_of(4096) {
+			if self
+				.options
+				.timeout
+				.is_some_and(|t| self.start.elapsed() >= t)
+			{
+				self.timed_out = true;
+			}
+			if self.options.is_cancelled() {
+				self.stop = true;
+			}
+			if self.reported.elapsed() >= PROGRESS_INTERVAL {
+				self.report(false);
+			}
+		}
+		self.timed_out || self.stop
+	}
+
+	/// Add the current path as solution, if it solves the cube
+	fn check_solution(&mut self) {
+		let turns: Vec<Turn> = self.path.iter().map(|i| turns_phase1[*i]).collect();
+
+		let mut cube = self.cube.clone();
+		for turn in turns.iter() {
+			cube.apply_turn(*turn);
+		}
+
+		if !cube.is_solved() || !self.solutions.insert(&turns) {
+			return;
+		}
+
+		if !(self.on_solution)(turns) || self.solutions.is_full() {
+			self.stop = true;
+		}
+	}
+
+	/// Search all solutions of exactly togo more turns, counted in the metric of the options.
+	/// Without coordinates, the search doesn't prune with the heuristics.
+	/// Return true if the search should be aborted.
+	fn search(&mut self, coords: Option<Coords>, togo: usize) -> bool {
+		if togo == 0 {
+			self.check_solution();
+			return self.stop;
+		}
+
+		self.nodes += 1;
+		if self.check_timeout() {
+			return true;
+		}
+
+		let last: Option<TurnType> = self.path.last().map(|i| turns_phase1[*i].side);
+		for (i, turn) in turns_phase1.iter().enumerate() {
+			if !is_allowed_after(last, turn.side) {
+				continue;
+			}
+
+			// The heuristics count turns, and every turn costs at least 1 in any metric
+			let cost = self.options.metric.cost(*turn);
+			if cost > togo {
+				continue;
+			}
+			let next = match coords {
+				Some(coords) => match coords.apply_within(i, togo - cost + 1) {
+					Some(next) => Some(next),
+					None => continue,
+				},
+				None => None,
+			};
+
+			self.path.push(i);
+			let abort = self.search(next, togo - cost);
+			self.path.pop();
+
+			if abort {
+				return true;
+			}
+		}
+
+		false
+	}
+}
+
+/// Search the shortest solutions of the cube in the metric of the options, in order of
+/// their length, and pass them to on_solution. The search stops once options.count
+/// solutions were found or on_solution returns false.
+///
+/// Solutions longer than options.max_length are not searched.
+/// The first search generates the huge pruning table (845MB), which takes a while, unless
+/// it searches at most TABLE_FREE_LEN turns. Cubes which need 17 turns take minutes,
+/// cubes which need 18 turns more than an hour.
+pub fn search(
+	cube: &CubieCube,
+	options: &SolveOptions,
+	on_solution: &mut dyn FnMut(Vec<Turn>) -> bool,
+) -> Result<(), SolveError> {
+	let max_len = options
+		.max_length
+		.unwrap_or(MAX_LEN * options.metric.max_side_cost());
+	// Short searches are faster without loading the tables
+	let coords = (max_len > TABLE_FREE_LEN).then(|| Coords::new(cube));
+
+	let mut search = Search {
+		cube,
+		options,
+		start: Instant::now(),
+		path: vec![],
+		solutions: SolutionSet::new(options.count, options.metric),
+		on_solution,
+		depth: 0,
+		nodes: 0,
+		reported: Instant::now(),
+		timed_out: false,
+		stop: false,
+	};
+
+	for depth in coords.map_or(0, |c| c.dist())..=max_len {
+		search.depth = depth;
+		search.search(coords, depth);
+		if search.stop {
+			return Ok(());
+		}
+		if search.timed_out {
+			break;
+		}
+
+		search.report(true);
+	}
+
+	match (search.solutions.is_empty(), search.timed_out) {
+		(false, _) => Ok(()),
+		(true, true) => Err(SolveError::Timeout),
+		(true, false) => Err(SolveError::NoSolution),
+	}
+}
+
+/// Find a shortest solution of the cube in the metric of the options.
+///
+/// Solutions longer than options.max_length are not searched (see `search`).
+pub fn solve(cube: &CubieCube, options: &SolveOptions) -> Result<Vec<Turn>, SolveError> {
+	let options = SolveOptions {
+		count: 1,
+		..options.clone()
+	};
+
+	let mut best = vec![];
+	search(cube, &options, &mut |turns| {
+		best = turns;
+		true
+	})?;
+	Ok(best)
+}
+
+/// An optimal solver using IDA* with a huge pruning table
+pub struct Optimal;
+
+impl Solver for Optimal {
+	fn name(&self) -> &'static str {
+		"Optimal"
+	}
+
+	fn description(&self) -> &'static str {
+		"IDA* over all face turns, finds the shortest solution but can take minutes"
+	}
+
+	fn solve(&self, cube: &CubieCube, options: &SolveOptions) -> Result<Solution, SolveError> {
+		cube.check_solvability()?;
+		solve(cube, options).map(Solution::from)
+	}
+
+	fn solutions(&self, cube: &CubieCube, options: &SolveOptions) -> Result<Solutions, SolveError> {
+		cube.check_solvability()?;
+
+		let cube = cube.clone();
+		Ok(stream_solutions(options, move |options, on_solution| {
+			search(&cube, options, on_solution)
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+
+	use rand::{rngs::StdRng, SeedableRng};
+
+	use super::*;
+	use crate::{parse_turns, scramble};
+
+	/// Return the cube after the sequence
+	fn cube_of(sequence: &str) -> CubieCube {
+		let mut cube = CubieCube::new();
+		for turn in parse_turns(sequence).unwrap() {
+			cube.apply_turn(turn);
+		}
+		cube
+	}
+
+	/// Return the length of the solution, after checking that it solves the cube
+	fn solution_len(cube: &CubieCube, options: &SolveOptions) -> Result<usize, SolveError> {
+		let solution = solve(cube, options)?;
+		let mut solved = cube.clone();
+		for turn in solution.iter() {
+			solved.apply_turn(*turn);
+		}
+		assert!(solved.is_solved());
+		Ok(solution.len())
+	}
+
+	#[test]
+	/// Short searches without the tables and searches with the tables find the same distance
+	fn solve_short() {
+		let table_free = SolveOptions {
+			max_length: Some(TABLE_FREE_LEN),
+			..SolveOptions::default()
+		};
+
+		for (sequence, dist) in [
+			("", 0),
+			("R", 1),
+			("R U", 2),
+			("R U R' U'", 4),
+			("F R' B2 L D'", 5),
+		] {
+			let cube = cube_of(sequence);
+			assert_eq!(
+				solution_len(&cube, &table_free).unwrap(),
+				dist,
+				"{sequence}"
+			);
+			assert_eq!(
+				solution_len(&cube, &SolveOptions::default()).unwrap(),
+				dist,
+				"{sequence}"
+			);
+		}
+	}
+
+	#[test]
+	/// Cubes beyond TABLE_FREE_LEN are solved optimally with the tables
+	fn solve_with_tables() {
+		let depths = Arc::new(Mutex::new(vec![]));
+		let reported = depths.clone();
+		let options = SolveOptions {
+			progress: Some(Arc::new(move |progress: &Progress| {
+				if progress.completed {
+					reported.lock().unwrap().push(progress.depth);
+				}
+			})),
+			..SolveOptions::default()
+		};
+
+		// The checkerboard pattern and the Sune
+		let mut searched = 0;
+		for (sequence, dist) in [("U2 D2 F2 B2 L2 R2", 6), ("R U R' U R U2 R'", 7)] {
+			let cube = cube_of(sequence);
+			assert_eq!(solution_len(&cube, &options).unwrap(), dist, "{sequence}");
+
+			// Only depths below the distance are searched completely,
+			// starting at the distance of the heuristics
+			let depths = std::mem::take(&mut *depths.lock().unwrap());
+			let expected: Vec<usize> = (dist - depths.len()..dist).collect();
+			assert_eq!(depths, expected, "{sequence}");
+			searched += depths.len();
+		}
+		assert!(searched > 0);
+	}
+
+	#[test]
+	/// Searches fail without a solution within max_length or in time
+	fn solve_errors() {
+		let sune = cube_of("R U R' U R U2 R'");
+		for max_length in [3, 6] {
+			let options = SolveOptions {
+				max_length: Some(max_length),
+				..SolveOptions::default()
+			};
+			assert!(matches!(
+				solve(&sune, &options),
+				Err(SolveError::NoSolution)
+			));
+		}
+
+		let cube = scramble::random_cube(&mut StdRng::seed_from_u64(42));
+		let options = SolveOptions {
+			timeout: Some(Duration::ZERO),
+			..SolveOptions::default()
+		};
+		assert!(matches!(solve(&cube, &options), Err(SolveError::Timeout)));
+	}
+}
@@ -0,0 +1,748 @@
+use std::{
+	fs,
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::RwLock,
+};
+
+use strum::IntoEnumIterator;
+
+use super::{kociemba, optimal};
+
+/*
+ * Storage of the pruning tables.
+ *
+ * The tables are saved in a directory which is (in this order of precedence)
+ * - set with `set_table_dir`, which the command line tool does with `--table-dir`
+ * - given by the environment variable RUBIKS_SOLVER_TABLE_DIR
+ * - the cache directory of the user: $XDG_CACHE_HOME/rubiks-solver or ~/.cache/rubiks-solver
+ * - "data" relative to the current directory, if there is no home directory
+ *
+ * Every file starts with a header:
+ *   magic (4 bytes) | format version (u32) | encoding (u32) | reserved (u32)
+ *   | rows (u64) | columns (u64) | checksum (u64)
+ * all in little endian, followed by the rows * columns entries in the given encoding.
+ * Files with a wrong header or size are treated as missing and regenerated.
+ *
+ * On Linux the files are memory mapped, so the tables aren't copied and processes share them
+ * through the page cache. Loading a table only checks its header and size, as the checksum
+ * would read every page of the file. The checksum is checked by `Table::verify`, which the
+ * `tables build` and `tables verify` commands use.
+ * Files are always replaced by renaming a new file, so mapped files are never modified.
+ * Elsewhere, or if mapping fails, the file is read.
+ *
+ * All tables contain the distance of a coordinate to the solved state.
+ * With the Mod3 encoding only the distance modulo 3 is stored (as in Kociemba's reference
+ * implementation). Tables which are too large for one byte per entry are always generated
+ * in this encoding. As the distances of neighbouring coordinates differ by at most one,
+ * the distance is recovered from the distance of a neighbour, or at the start of a search,
+ * by following decreasing distances to the solved state.
+ */
+
+/// The environment variable which sets the table directory
+pub const TABLE_DIR_ENV: &str = "RUBIKS_SOLVER_TABLE_DIR";
+
+/// Must be increased whenever the content or the layout of a table changes
+pub const FORMAT_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 4] = b"RSPT";
+const HEADER_LEN: usize = 40;
+
+/// The value of entries which weren't reached by the generation
+pub(crate) const UNVISITED: u8 = u8::MAX;
+
+/// All pruning tables
+const TABLES: &[Table] = &[
+	kociemba::PHASE1_TABLE,
+	kociemba::PHASE2_TABLE,
+	optimal::CORNER_TABLE,
+	optimal::HUGE_TABLE,
+];
+
+static TABLE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+static ENCODING: RwLock<Encoding> = RwLock::new(Encoding::Byte);
+
+/// How the distances of a table are stored
+#[derive(
+	Clone, Copy, Debug, Default, PartialEq, Eq, strum::EnumIter, strum::EnumString, strum::Display,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum Encoding {
+	/// One byte per entry
+	#[default]
+	Byte,
+	/// Two entries per byte. Distances above 14 are stored as 14,
+	/// which keeps the table a lower bound.
+	Nibble,
+	/// Four entries per byte, which only store the distance modulo 3
+	Mod3,
+}
+
+impl Encoding {
+	/// The number of entries stored in one byte
+	const fn per_byte(self) -> usize {
+		match self {
+			Encoding::Byte => 1,
+			Encoding::Nibble => 2,
+			Encoding::Mod3 => 4,
+		}
+	}
+
+	/// The number of bytes needed for len entries
+	pub const fn size(self, len: usize) -> usize {
+		len.div_ceil(self.per_byte())
+	}
+}
+
+/// A read-only memory mapping of a whole file
+#[cfg(target_os = "linux")]
+struct Mmap {
+	ptr: *mut libc::c_void,
+	len: usize,
+}
+
+// SAFETY: The mapping is read-only and owned by this struct
+#[cfg(target_os = "linux")]
+unsafe impl Send for Mmap {}
+#[cfg(target_os = "linux")]
+unsafe impl Sync for Mmap {}
+
+#[cfg(target_os = "linux")]
+impl Mmap {
+	fn open(path: &Path) -> io::Result<Self> {
+		use std::os::fd::AsRawFd;
+
+		let file = fs::File::open(path)?;
+		let len = file.metadata()?.len() as usize;
+		if len == 0 {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "empty file"));
+		}
+
+		// SAFETY: A private read-only mapping of a valid file descriptor,
+		// the mapping stays valid after the file is closed.
+		let ptr = unsafe {
+			libc::mmap(
+				std::ptr::null_mut(),
+				len,
+				libc::PROT_READ,
+				libc::MAP_PRIVATE,
+				file.as_raw_fd(),
+				0,
+			)
+		};
+		if ptr == libc::MAP_FAILED {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(Self { ptr, len })
+	}
+}
+
+#[cfg(target_os = "linux")]
+impl std::ops::Deref for Mmap {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		// SAFETY: The mapping is readable for len bytes until it is dropped
+		unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+	}
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Mmap {
+	fn drop(&mut self) {
+		// SAFETY: The mapping was created by mmap with this length
+		unsafe {
+			libc::munmap(self.ptr, self.len);
+		}
+	}
+}
+
+/// The bytes of a table, which are either in memory or a mapped file
+enum Data {
+	Owned(Vec<u8>),
+	#[cfg(target_os = "linux")]
+	Mapped(Mmap),
+}
+
+impl std::ops::Deref for Data {
+	type Target = [u8];
+
+	#[inline]
+	fn deref(&self) -> &[u8] {
+		match self {
+			Data::Owned(data) => data,
+			#[cfg(target_os = "linux")]
+			Data::Mapped(map) => map,
+		}
+	}
+}
+
+/// A table of distances in one of the encodings
+pub struct PruningTable {
+	encoding: Encoding,
+	len: usize,
+	data: Data,
+	/// The position of the first entry in data
+	offset: usize,
+}
+
+impl PruningTable {
+	/// The value of unvisited entries in the nibble encoding
+	const NIBBLE_UNVISITED: u8 = 15;
+	/// The value of unvisited entries in the mod 3 encoding
+	const MOD3_UNVISITED: u8 = 3;
+
+	/// Create a table from entries, which are already in the given encoding
+	pub(crate) fn from_bytes(data: Vec<u8>, len: usize, encoding: Encoding) -> Self {
+		debug_assert_eq!(data.len(), encoding.size(len));
+
+		Self {
+			encoding,
+			len,
+			data: Data::Owned(data),
+			offset: 0,
+		}
+	}
+
+	/// Store the distances in the given encoding
+	pub fn encode(dists: &[u8], encoding: Encoding) -> Self {
+		let data = match encoding {
+			Encoding::Byte => dists.to_vec(),
+			Encoding::Nibble => dists
+				.chunks(2)
+				.map(|chunk| {
+					chunk.iter().rev().fold(0, |byte, d| {
+						let d = match *d {
+							UNVISITED => Self::NIBBLE_UNVISITED,
+							d => d.min(Self::NIBBLE_UNVISITED - 1),
+						};
+						byte << 4 | d
+					})
+				})
+				.collect(),
+			Encoding::Mod3 => dists
+				.chunks(4)
+				.map(|chunk| {
+					chunk.iter().rev().fold(0, |byte, d| {
+						let d = match *d {
+							UNVISITED => Self::MOD3_UNVISITED,
+							d => d % 3,
+						};
+						byte << 2 | d
+					})
+				})
+				.collect(),
+		};
+
+		Self {
+			encoding,
+			len: dists.len(),
+			data: Data::Owned(data),
+			offset: 0,
+		}
+	}
+
+	pub fn encoding(&self) -> Encoding {
+		self.encoding
+	}
+
+	/// The number of entries
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// The encoded entries
+	#[inline]
+	pub fn bytes(&self) -> &[u8] {
+		&self.data[self.offset..]
+	}
+
+	/// Return the stored value of an entry: the distance, or with the mod 3
+	/// encoding the distance modulo 3. Unvisited entries return UNVISITED.
+	#[inline]
+	pub fn get(&self, idx: usize) -> u8 {
+		let data = self.bytes();
+		match self.encoding {
+			Encoding::Byte => data[idx],
+			Encoding::Nibble => match (data[idx / 2] >> (4 * (idx % 2))) & 15 {
+				Self::NIBBLE_UNVISITED => UNVISITED,
+				d => d,
+			},
+			Encoding::Mod3 => match (data[idx / 4] >> (2 * (idx % 4))) & 3 {
+				Self::MOD3_UNVISITED => UNVISITED,
+				d => d,
+			},
+		}
+	}
+
+	/// Return the stored values of all entries
+	pub fn values(&self) -> impl Iterator<Item = u8> + '_ {
+		(0..self.len).map(|idx| self.get(idx))
+	}
+
+	/// Return the distance of an entry, given the distance of an entry one turn away
+	#[inline]
+	pub fn dist_near(&self, idx: usize, near: usize) -> usize {
+		match self.encoding {
+			Encoding::Mod3 => match (self.get(idx) as usize + 3 - near % 3) % 3 {
+				0 => near,
+				1 => near + 1,
+				_ => near - 1,
+			},
+			_ => self.get(idx) as usize,
+		}
+	}
+
+	/// Return the distance of the coordinate `coord`, whose entry is index(coord).
+	/// apply(coord, i) is the coordinate after the turn i of all `turns`.
+	///
+	/// Only the solved coordinate may have the index 0.
+	/// With the mod 3 encoding this follows decreasing distances to the
+	/// solved coordinate, so it should only be used at the start of a search.
+	pub fn dist<C: Copy>(
+		&self,
+		coord: C,
+		index: impl Fn(C) -> usize,
+		turns: usize,
+		apply: impl Fn(C, usize) -> C,
+	) -> usize {
+		if self.encoding != Encoding::Mod3 {
+			return self.get(index(coord)) as usize;
+		}
+
+		let mut coord = coord;
+		let mut dist = 0;
+		while index(coord) != 0 {
+			let lower = (self.get(index(coord)) + 2) % 3;
+			coord = (0..turns)
+				.map(|i| apply(coord, i))
+				.find(|c| self.get(index(*c)) == lower)
+				.expect("A pruning table is inconsistent");
+			dist += 1;
+		}
+		dist
+	}
+}
+
+/// Set the encoding of tables which are generated from now on.
+/// Tables which are loaded from a file keep their encoding.
+pub fn set_encoding(encoding: Encoding) {
+	*ENCODING.write().unwrap() = encoding;
+}
+
+/// Return the encoding of tables which are generated
+pub fn encoding() -> Encoding {
+	*ENCODING.read().unwrap()
+}
+
+/// All the reasons why a table file could not be used
+#[derive(thiserror::Error, Debug)]
+pub enum TableError {
+	#[error("{0}")]
+	Io(#[from] io::Error),
+	#[error("The file is not a table file")]
+	InvalidHeader,
+	#[error("The table has format version {0}, but version {FORMAT_VERSION} is needed")]
+	Version(u32),
+	#[error("The table has the unknown encoding {0}")]
+	Encoding(u32),
+	#[error("The table has dimensions {0:?}, but {1:?} are needed")]
+	Dimensions([usize; 2], [usize; 2]),
+	#[error("The table is truncated")]
+	Truncated,
+	#[error("The checksum of the table doesn't match its content")]
+	Checksum,
+	#[error("The table has {0} entries without a distance")]
+	Incomplete(usize),
+}
+
+/// How a table is generated. Both report the progress as (done, total) steps.
+#[derive(Clone, Copy)]
+pub(crate) enum Generator {
+	/// Generate the distance of every entry, which are stored in any encoding
+	Distances(fn(&mut dyn FnMut(usize, usize)) -> Vec<u8>),
+	/// Generate the table in the mod 3 encoding,
+	/// for tables which are too large for one byte per entry
+	Mod3(fn(&mut dyn FnMut(usize, usize)) -> PruningTable),
+}
+
+/// A pruning table which is stored in the table directory
+pub struct Table {
+	/// The name of the file
+	pub name: &'static str,
+	/// What the table is used for
+	pub description: &'static str,
+	/// The number of rows and columns
+	pub dims: [usize; 2],
+	pub(crate) generate: Generator,
+}
+
+impl Table {
+	/// Return the path of the table file
+	pub fn path(&self) -> PathBuf {
+		table_path(self.name)
+	}
+
+	/// Load the table and check the header and the size of the file
+	pub fn load(&self) -> Result<PruningTable, TableError> {
+		load(&self.path(), self.dims)
+	}
+
+	/// Load the table, check the checksum of the file and that every entry has a distance
+	pub fn verify(&self) -> Result<PruningTable, TableError> {
+		let table = self.load()?;
+		check_sum(&table)?;
+		match table.values().filter(|d| *d == UNVISITED).count() {
+			0 => Ok(table),
+			n => Err(TableError::Incomplete(n)),
+		}
+	}
+
+	/// Return the encoding in which the table is generated, if the given one is requested
+	pub fn encoding(&self, encoding: Encoding) -> Encoding {
+		match self.generate {
+			Generator::Distances(_) => encoding,
+			Generator::Mod3(_) => Encoding::Mod3,
+		}
+	}
+
+	/// Generate the table in the given encoding (see `encoding`)
+	/// and report the progress as (done, total) steps
+	pub fn generate(
+		&self,
+		progress: &mut dyn FnMut(usize, usize),
+		encoding: Encoding,
+	) -> PruningTable {
+		match self.generate {
+			Generator::Distances(generate) => PruningTable::encode(&generate(progress), encoding),
+			Generator::Mod3(generate) => generate(progress),
+		}
+	}
+
+	/// Save the table.
+	/// The directory is created if needed and the file is replaced atomically,
+	/// so an interrupted save never leaves a truncated table behind.
+	pub fn save(&self, table: &PruningTable) -> Result<(), TableError> {
+		save(&self.path(), self.dims, table)
+	}
+
+	/// Load the table, or generate and save it if it is missing or invalid
+	pub(crate) fn load_or_generate(&self) -> PruningTable {
+		self.load_or_generate_at(&self.path())
+	}
+
+	/// Load the table from the given file, or generate and save it there
+	fn load_or_generate_at(&self, path: &Path) -> PruningTable {
+		match load(path, self.dims) {
+			Ok(table) => return table,
+			Err(TableError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {}
+			Err(e) => eprintln!(
+				"The heuristics for {} are invalid ({}), rebuilding",
+				self.description, e
+			),
+		}
+
+		eprintln!(
+			"Must generate heuristics for {}, please wait...",
+			self.description
+		);
+		let table = self.generate(&mut |_, _| {}, encoding());
+
+		if let Err(e) = save(path, self.dims, &table) {
+			eprintln!(
+				"Could not save heuristics for {} to {}: {}",
+				self.description,
+				path.display(),
+				e
+			);
+		}
+
+		table
+	}
+}
+
+/// Load the helper table with the given name and dimensions in bytes, or generate and save it.
+/// Helper tables (like move tables) are generated in a fraction of a second,
+/// but loading them is still much faster for short-lived processes.
+pub(crate) fn load_or_generate_bytes(
+	name: &str,
+	dims: [usize; 2],
+	generate: impl FnOnce() -> Vec<u8>,
+) -> Vec<u8> {
+	let path = table_path(name);
+	if let Ok(file) = fs::read(&path) {
+		// They are read completely anyway, so the checksum costs little
+		if let Ok(table) = parse(Data::Owned(file), dims) {
+			if check_sum(&table).is_ok() {
+				return table.bytes().to_vec();
+			}
+		}
+	}
+
+	let data = generate();
+	// Failing to save is not worth a warning, it only costs time on the next start
+	let _ = save(&path, dims, &PruningTable::encode(&data, Encoding::Byte));
+	data
+}
+
+/// Return all pruning tables
+pub fn tables() -> &'static [Table] {
+	TABLES
+}
+
+/// Return how many entries of the table have each stored value (v[value] = count),
+/// which is the distance or with the mod 3 encoding the distance modulo 3.
+/// Unvisited entries are not counted.
+pub fn depth_distribution(table: &PruningTable) -> Vec<usize> {
+	let mut out = vec![0; 256];
+	for d in table.values() {
+		out[d as usize] += 1;
+	}
+
+	out.truncate(UNVISITED as usize);
+	while out.last() == Some(&0) {
+		out.pop();
+	}
+	out
+}
+
+/// Set the directory where the tables are read from and saved to.
+/// This has no effect on tables which were already loaded.
+pub fn set_table_dir(dir: impl Into<PathBuf>) {
+	*TABLE_DIR.write().unwrap() = Some(dir.into());
+}
+
+/// Return the directory where the tables are read from and saved to
+pub fn table_dir() -> PathBuf {
+	if let Some(dir) = TABLE_DIR.read().unwrap().as_ref() {
+		return dir.clone();
+	}
+
+	if let Some(dir) = std::env::var_os(TABLE_DIR_ENV).filter(|d| !d.is_empty()) {
+		return dir.into();
+	}
+
+	let cache = std::env::var_os("XDG_CACHE_HOME")
+		.filter(|d| !d.is_empty())
+		.map(PathBuf::from)
+		.or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")));
+
+	match cache {
+		Some(cache) => cache.join("rubiks-solver"),
+		None => PathBuf::from("data"),
+	}
+}
+
+/// Return the path of the table file with the given name
+fn table_path(name: &str) -> PathBuf {
+	table_dir().join(name)
+}
+
+/// A fast checksum (FNV-1a over 64 bit words)
+fn checksum(data: &[u8]) -> u64 {
+	const PRIME: u64 = 0x100000001b3;
+
+	let mut chunks = data.chunks_exact(8);
+	let mut hash: u64 = 0xcbf29ce484222325;
+	for chunk in chunks.by_ref() {
+		hash = (hash ^ u64::from_le_bytes(chunk.try_into().unwrap())).wrapping_mul(PRIME);
+	}
+	for byte in chunks.remainder() {
+		hash = (hash ^ *byte as u64).wrapping_mul(PRIME);
+	}
+
+	hash
+}
+
+/// Write the table with its header to the given path
+fn save(path: &Path, dims: [usize; 2], table: &PruningTable) -> Result<(), TableError> {
+	debug_assert_eq!(dims[0] * dims[1], table.len());
+	let data = table.bytes();
+
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+
+	let mut header = Vec::with_capacity(HEADER_LEN);
+	header.extend_from_slice(MAGIC);
+	header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+	header.extend_from_slice(&(table.encoding() as u32).to_le_bytes());
+	header.extend_from_slice(&0u32.to_le_bytes());
+	header.extend_from_slice(&(dims[0] as u64).to_le_bytes());
+	header.extend_from_slice(&(dims[1] as u64).to_le_bytes());
+	header.extend_from_slice(&checksum(data).to_le_bytes());
+
+	let tmp = path.with_extension("tmp");
+	let mut file = fs::File::create(&tmp)?;
+	file.write_all(&header)?;
+	file.write_all(data)?;
+	file.sync_all()?;
+	fs::rename(tmp, path)?;
+
+	Ok(())
+}
+
+/// Load the table file at the given path and check its header and size
+fn load(path: &Path, dims: [usize; 2]) -> Result<PruningTable, TableError> {
+	#[cfg(target_os = "linux")]
+	if let Ok(map) = Mmap::open(path) {
+		return parse(Data::Mapped(map), dims);
+	}
+
+	parse(Data::Owned(fs::read(path)?), dims)
+}
+
+/// Read the u64 at the given position of the header
+fn header_u64(header: &[u8], pos: usize) -> u64 {
+	u64::from_le_bytes(header[pos..pos + 8].try_into().unwrap())
+}
+
+/// Check the header and the size of a table file and return the table
+fn parse(file: Data, dims: [usize; 2]) -> Result<PruningTable, TableError> {
+	if file.len() < HEADER_LEN || &file[0..4] != MAGIC {
+		return Err(TableError::InvalidHeader);
+	}
+
+	let header = &file[..HEADER_LEN];
+	let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+	if version != FORMAT_VERSION {
+		return Err(TableError::Version(version));
+	}
+
+	let code = u32::from_le_bytes(header[8..12].try_into().unwrap());
+	let encoding = Encoding::iter()
+		.find(|e| *e as u32 == code)
+		.ok_or(TableError::Encoding(code))?;
+
+	let found = [
+		header_u64(header, 16) as usize,
+		header_u64(header, 24) as usize,
+	];
+	if found != dims {
+		return Err(TableError::Dimensions(found, dims));
+	}
+
+	let len = dims[0] * dims[1];
+	if file.len() != HEADER_LEN + encoding.size(len) {
+		return Err(TableError::Truncated);
+	}
+
+	Ok(PruningTable {
+		encoding,
+		len,
+		data: file,
+		offset: HEADER_LEN,
+	})
+}
+
+/// Check the checksum of a table, which was loaded from a file
+fn check_sum(table: &PruningTable) -> Result<(), TableError> {
+	debug_assert_eq!(table.offset, HEADER_LEN);
+
+	if checksum(table.bytes()) != header_u64(&table.data[..HEADER_LEN], 32) {
+		return Err(TableError::Checksum);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	/// Damaged or mismatching headers must be detected
+	fn header_validation() {
+		let table = PruningTable::encode(&(0..60).collect::<Vec<u8>>(), Encoding::Byte);
+		let path = std::env::temp_dir().join(format!("rubiks-solver-test-{}", std::process::id()));
+		save(&path, [6, 10], &table).unwrap();
+		let file = fs::read(&path).unwrap();
+
+		#[cfg(target_os = "linux")]
+		{
+			let map = Data::Mapped(Mmap::open(&path).unwrap());
+			assert_eq!(parse(map, [6, 10]).unwrap().bytes(), table.bytes());
+		}
+
+		fs::remove_file(path).unwrap();
+		let parse = |file: Vec<u8>, dims| parse(Data::Owned(file), dims);
+
+		assert_eq!(parse(file.clone(), [6, 10]).unwrap().bytes(), table.bytes());
+		assert!(matches!(
+			parse(file.clone(), [10, 6]),
+			Err(TableError::Dimensions(..))
+		));
+		assert!(matches!(
+			parse(file[..file.len() - 1].to_vec(), [6, 10]),
+			Err(TableError::Truncated)
+		));
+
+		let mut damaged = file.clone();
+		damaged[HEADER_LEN + 3] ^= 1;
+		let damaged = parse(damaged, [6, 10]).unwrap();
+		assert!(matches!(check_sum(&damaged), Err(TableError::Checksum)));
+
+		let mut old = file;
+		old[4] = 0;
+		assert!(matches!(parse(old, [6, 10]), Err(TableError::Version(0))));
+	}
+
+	#[test]
+	/// A truncated table file must be rebuilt, also if it is memory mapped
+	fn rebuild_damaged() {
+		let table = Table {
+			name: "test",
+			description: "the test",
+			dims: [6, 10],
+			generate: Generator::Distances(|_| (0..60).collect()),
+		};
+		let path =
+			std::env::temp_dir().join(format!("rubiks-solver-test-rebuild-{}", std::process::id()));
+		let dists: Vec<u8> = table.load_or_generate_at(&path).values().collect();
+		assert_eq!(dists, (0..60).collect::<Vec<u8>>());
+
+		let file = fs::read(&path).unwrap();
+		fs::write(&path, &file[..file.len() - 1]).unwrap();
+		assert!(matches!(load(&path, [6, 10]), Err(TableError::Truncated)));
+
+		let rebuilt: Vec<u8> = table.load_or_generate_at(&path).values().collect();
+		assert_eq!(rebuilt, dists);
+		assert!(load(&path, [6, 10]).is_ok());
+
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	/// The packed encodings must store the distances (within their limits)
+	fn encodings() {
+		let dists: Vec<u8> = (0..19).chain([UNVISITED, 3, 1]).collect();
+
+		for encoding in Encoding::iter() {
+			let table = PruningTable::encode(&dists, encoding);
+			assert_eq!(table.bytes().len(), encoding.size(dists.len()));
+
+			for (idx, d) in dists.iter().enumerate() {
+				let expected = match (encoding, *d) {
+					(_, UNVISITED) => UNVISITED,
+					(Encoding::Byte, d) => d,
+					(Encoding::Nibble, d) => d.min(14),
+					(Encoding::Mod3, d) => d % 3,
+				};
+				assert_eq!(table.get(idx), expected);
+			}
+		}
+
+		// A path of distances 0, 1, ..., 18 where entry i is one turn away from i - 1
+		let table = PruningTable::encode(&dists, Encoding::Mod3);
+		for i in 1..19 {
+			assert_eq!(table.dist_near(i, i - 1), i);
+			assert_eq!(table.dist_near(i - 1, i), i - 1);
+		}
+		let dist = table.dist(18, |c| c, 2, |c, i| if i == 0 { c - 1 } else { c + 1 });
+		assert_eq!(dist, 18);
+	}
+}
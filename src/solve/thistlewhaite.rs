@@ -1,17 +1,25 @@
-use crate::cube::{arraycube::ArrayCube, turn::*, *};
+use crate::cube::{
+	arraycube::ArrayCube, cubiecube::CubieCube, notation::*, sequence::Sequence, turn::*, *,
+};
 use std::collections::{HashMap, VecDeque};
 use strum::*;
 
+use super::{Solution, SolveError, SolveOptions, Solver};
+
 /// Simple helper struct to have keep track of the legal moves
-#[derive(Default)]
 struct TurnSet {
 	set: [bool; NUM_TURNTYPES * NUM_TURNWISES],
 }
 
 impl TurnSet {
-	/// Create a full set with all possible turns in the set.
+	/// Create a full set with all turns of the outer sides in the set.
 	pub fn new() -> Self {
-		let set = [true; NUM_TURNTYPES * NUM_TURNWISES];
+		let mut set = [true; NUM_TURNTYPES * NUM_TURNWISES];
+		for side in TurnType::iter().filter(|t| !t.is_side()) {
+			for wise in TurnWise::iter() {
+				set[Self::hash(Turn { side, wise })] = false;
+			}
+		}
 		Self { set }
 	}
 
@@ -227,35 +235,6 @@ fn hash_phase4(cube: &ArrayCube) -> u64 {
 	hash
 }
 
-/// Do optimizations to the solving sequence to shorten in.
-fn post_solve_optimization(turns: std::vec::Vec<Turn>) -> std::vec::Vec<Turn> {
-	let mut out: std::vec::Vec<Turn> = vec![];
-
-	for turn in turns {
-		if let Some(t) = out.last() {
-			// If 2 following turns turn the same side, combine it into one single turn
-			if t.side == turn.side {
-				let new = (t.wise as usize + turn.wise as usize + 2) % (NUM_TURNWISES + 1);
-				let idx = out.len() - 1;
-				match new {
-					0 => {
-						let _ = out.pop();
-					}
-					1 => out[idx].wise = TurnWise::Clockwise,
-					2 => out[idx].wise = TurnWise::Double,
-					3 => out[idx].wise = TurnWise::CounterClockwise,
-					_ => {}
-				}
-				continue;
-			}
-		}
-
-		out.push(turn);
-	}
-
-	out
-}
-
 /// Solve the cube using the thistlewaite algorithm and return the solving-sequence.
 ///
 /// cube: The cube to solve
@@ -296,7 +275,27 @@ pub fn solve(cube: ArrayCube) -> Option<Vec<Turn>> {
 	let mut test = cube;
 	test.apply_turns(seq.clone());
 	assert_eq!(test, solved); // superfluous, but to be sure...
-	seq = post_solve_optimization(seq); // Do some optimization to the sequence afterwards.
+	seq = Sequence::from(seq).simplify().into(); // Do some optimization to the sequence afterwards.
 
 	Some(seq)
 }
+
+/// Thistlewaite's four-phase algorithm
+pub struct Thistlewaite;
+
+impl Solver for Thistlewaite {
+	fn name(&self) -> &'static str {
+		"Thistlewaite"
+	}
+
+	fn description(&self) -> &'static str {
+		"Thistlewaite's four-phase algorithm, needs no tables but finds long solutions"
+	}
+
+	fn solve(&self, cube: &CubieCube, _options: &SolveOptions) -> Result<Solution, SolveError> {
+		cube.check_solvability()?;
+		solve(cube.clone().into())
+			.map(Solution::from)
+			.ok_or(SolveError::NoSolution)
+	}
+}